@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS cluster_status (
+    kubeconfig_id TEXT PRIMARY KEY NOT NULL,
+    reachable INTEGER NOT NULL DEFAULT 0,
+    auth_valid INTEGER NOT NULL DEFAULT 0,
+    server_version TEXT,
+    latency_ms INTEGER,
+    error_msg TEXT,
+    checked_at TEXT NOT NULL,
+    FOREIGN KEY (kubeconfig_id) REFERENCES kubeconfigs(id) ON DELETE CASCADE
+)
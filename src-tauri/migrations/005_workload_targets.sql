@@ -0,0 +1,44 @@
+CREATE TABLE favorites_new (
+    id TEXT PRIMARY KEY NOT NULL,
+    kubeconfig_id TEXT NOT NULL,
+    namespace TEXT NOT NULL,
+    resource_type TEXT NOT NULL CHECK (resource_type IN ('service', 'pod', 'deployment', 'statefulset', 'selector')),
+    resource_name TEXT NOT NULL,
+    remote_port INTEGER NOT NULL,
+    local_port INTEGER,
+    label TEXT NOT NULL,
+    group_name TEXT,
+    FOREIGN KEY (kubeconfig_id) REFERENCES kubeconfigs(id) ON DELETE CASCADE
+);
+
+INSERT INTO favorites_new (id, kubeconfig_id, namespace, resource_type, resource_name, remote_port, local_port, label, group_name)
+    SELECT id, kubeconfig_id, namespace, resource_type, resource_name, remote_port, local_port, label, group_name FROM favorites;
+
+DROP TABLE favorites;
+
+ALTER TABLE favorites_new RENAME TO favorites;
+
+CREATE TABLE active_forwards_new (
+    id TEXT PRIMARY KEY NOT NULL,
+    favorite_id TEXT,
+    kubeconfig_id TEXT NOT NULL,
+    namespace TEXT NOT NULL,
+    resource_type TEXT NOT NULL CHECK (resource_type IN ('service', 'pod', 'deployment', 'statefulset', 'selector')),
+    resource_name TEXT NOT NULL,
+    target_pod TEXT,
+    remote_port INTEGER NOT NULL,
+    local_port INTEGER NOT NULL,
+    pid INTEGER,
+    status TEXT NOT NULL DEFAULT 'stopped' CHECK (status IN ('running', 'error', 'stopped')),
+    started_at TEXT,
+    error_msg TEXT,
+    FOREIGN KEY (favorite_id) REFERENCES favorites(id) ON DELETE SET NULL,
+    FOREIGN KEY (kubeconfig_id) REFERENCES kubeconfigs(id) ON DELETE CASCADE
+);
+
+INSERT INTO active_forwards_new (id, favorite_id, kubeconfig_id, namespace, resource_type, resource_name, remote_port, local_port, pid, status, started_at, error_msg)
+    SELECT id, favorite_id, kubeconfig_id, namespace, resource_type, resource_name, remote_port, local_port, pid, status, started_at, error_msg FROM active_forwards;
+
+DROP TABLE active_forwards;
+
+ALTER TABLE active_forwards_new RENAME TO active_forwards
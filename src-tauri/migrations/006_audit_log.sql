@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS audit_log (
+    id TEXT PRIMARY KEY NOT NULL,
+    kubeconfig_id TEXT NOT NULL,
+    kubeconfig_name TEXT,
+    namespace TEXT NOT NULL,
+    action TEXT NOT NULL,
+    target TEXT NOT NULL,
+    detail TEXT,
+    outcome TEXT NOT NULL CHECK (outcome IN ('success', 'error')),
+    error_msg TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+)
@@ -0,0 +1,5 @@
+ALTER TABLE pg_connections ADD COLUMN ssl_ca_secret_id TEXT;
+
+ALTER TABLE pg_connections ADD COLUMN ssl_cert_secret_id TEXT;
+
+ALTER TABLE pg_connections ADD COLUMN ssl_key_secret_id TEXT
@@ -0,0 +1 @@
+ALTER TABLE pg_connections ADD COLUMN statement_timeout_ms INTEGER
@@ -0,0 +1 @@
+ALTER TABLE pg_saved_queries ADD COLUMN params_json TEXT
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use sqlx::sqlite::{SqliteConnection, SqlitePool, SqlitePoolOptions};
 use std::path::PathBuf;
 
 pub async fn init_db(app_dir: PathBuf) -> Result<SqlitePool> {
@@ -27,6 +27,9 @@ pub async fn init_db(app_dir: PathBuf) -> Result<SqlitePool> {
         include_str!("../migrations/001_init.sql"),
         include_str!("../migrations/002_ngrok.sql"),
         include_str!("../migrations/003_pg_connections.sql"),
+        include_str!("../migrations/004_cluster_status.sql"),
+        // 005 is a one-off table rebuild, see migrate_workload_targets()
+        include_str!("../migrations/006_audit_log.sql"),
     ];
 
     for migration_sql in migrations {
@@ -38,5 +41,86 @@ pub async fn init_db(app_dir: PathBuf) -> Result<SqlitePool> {
         }
     }
 
+    migrate_workload_targets(&pool).await?;
+
+    // Column additions, safe to re-run
+    let column_migrations = [
+        include_str!("../migrations/007_pg_tls.sql"),
+        include_str!("../migrations/008_pg_statement_timeout.sql"),
+        include_str!("../migrations/009_pg_saved_query_params.sql"),
+    ];
+    for migration_sql in column_migrations {
+        add_columns(&pool, migration_sql).await?;
+    }
+
     Ok(pool)
 }
+
+/// Run a migration made of `ALTER TABLE ... ADD COLUMN` statements.
+///
+/// SQLite has no `ADD COLUMN IF NOT EXISTS`, so statements adding a column
+/// that already exists are skipped.
+async fn add_columns(pool: &SqlitePool, migration_sql: &str) -> Result<()> {
+    for statement in migration_sql.split(';') {
+        let trimmed = statement.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Err(e) = sqlx::query(trimmed).execute(pool).await {
+            if !e.to_string().contains("duplicate column name") {
+                return Err(e.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Widen the `resource_type` CHECK constraints on `favorites` and
+/// `active_forwards` to accept workloads and label selectors.
+///
+/// SQLite cannot alter a CHECK constraint, so both tables are rebuilt. This
+/// only runs when the old constraint is still in place, on a single
+/// connection with foreign keys disabled so dropping the old tables does not
+/// cascade into `pg_connections` or `active_forwards`.
+async fn migrate_workload_targets(pool: &SqlitePool) -> Result<()> {
+    let (favorites_sql,): (String,) = sqlx::query_as(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'favorites'",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if favorites_sql.contains("'deployment'") {
+        return Ok(());
+    }
+
+    let mut conn = pool.acquire().await?;
+    sqlx::query("PRAGMA foreign_keys=OFF;")
+        .execute(&mut *conn)
+        .await?;
+
+    let rebuilt = rebuild_workload_tables(&mut conn).await;
+
+    // The connection goes back to the pool, so foreign keys must be back on
+    // whether or not the rebuild worked. If that fails, close it instead.
+    if let Err(e) = sqlx::query("PRAGMA foreign_keys=ON;")
+        .execute(&mut *conn)
+        .await
+    {
+        drop(conn.detach());
+        return Err(e.into());
+    }
+
+    rebuilt
+}
+
+async fn rebuild_workload_tables(conn: &mut SqliteConnection) -> Result<()> {
+    let mut tx = sqlx::Connection::begin(conn).await?;
+    for statement in include_str!("../migrations/005_workload_targets.sql").split(';') {
+        let trimmed = statement.trim();
+        if !trimmed.is_empty() {
+            sqlx::query(trimmed).execute(&mut *tx).await?;
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,268 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::sqlite::SqlitePool;
+use tauri::State;
+
+use crate::auth::VaultState;
+use crate::favorites::delete_favorite;
+use crate::forward::{
+    create_forward, find_running_forward, kill_forward, wait_for_local_port, ActiveForward,
+};
+use crate::k8s::build_client;
+use crate::k8s_credentials::{detect_credentials, DetectedCredentials, SuggestedPort};
+use crate::pgmanager::{pg_connect, pg_delete_connection, pg_save_connection, PgConnection};
+use crate::AppState;
+
+/// Defaults used when the detected credentials leave a field unset.
+const DEFAULT_DATABASE: &str = "postgres";
+const DEFAULT_USERNAME: &str = "postgres";
+const DEFAULT_SSL_MODE: &str = "prefer";
+
+/// How long to wait for a new kubectl port-forward to start listening.
+const FORWARD_READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ---------------------------------------------------------------------------
+// Models
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+pub struct ProvisionedDbAccess {
+    pub connection: PgConnection,
+    pub forward: ActiveForward,
+    pub favorite_id: String,
+    /// Status message from `pg_connect`.
+    pub message: String,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Pick the Service port a detected credential set should be forwarded to.
+///
+/// The detected host (`db`, `db.ns.svc.cluster.local`, ...) must name a
+/// Service in the namespace. Without a host, the best suggested port is used.
+fn match_service(cred: &DetectedCredentials) -> Result<&SuggestedPort, String> {
+    match cred.host.as_deref() {
+        Some(host) => {
+            let service = host.split('.').next().unwrap_or_default();
+            cred.suggested_ports
+                .iter()
+                .find(|p| p.service_name == service)
+                .ok_or_else(|| {
+                    format!("Host '{host}' does not match a database service in this namespace")
+                })
+        }
+        None => cred
+            .suggested_ports
+            .first()
+            .ok_or_else(|| "No service in this namespace exposes a Postgres port".to_string()),
+    }
+}
+
+/// Find the favorite for a service port, creating it if needed. Returns its
+/// ID and whether it was created.
+async fn ensure_favorite(
+    pool: &SqlitePool,
+    kubeconfig_id: &str,
+    namespace: &str,
+    target: &SuggestedPort,
+    label: &str,
+) -> Result<(String, bool), String> {
+    let existing: Option<(String,)> = sqlx::query_as(
+        "SELECT id FROM favorites WHERE kubeconfig_id = ? AND namespace = ? AND resource_type = 'service' AND resource_name = ? AND remote_port = ?",
+    )
+    .bind(kubeconfig_id)
+    .bind(namespace)
+    .bind(&target.service_name)
+    .bind(target.port)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if let Some((id,)) = existing {
+        return Ok((id, false));
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO favorites (id, kubeconfig_id, namespace, resource_type, resource_name, remote_port, local_port, label, group_name) VALUES (?, ?, ?, 'service', ?, ?, NULL, ?, NULL)",
+    )
+    .bind(&id)
+    .bind(kubeconfig_id)
+    .bind(namespace)
+    .bind(&target.service_name)
+    .bind(target.port)
+    .bind(label)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save favorite: {e}"))?;
+
+    Ok((id, true))
+}
+
+/// Undo a favorite created by a failed `provision_db_access`, with the
+/// forward and connection made for it. Best-effort: errors are logged.
+async fn discard_favorite(state: &State<'_, AppState>, favorite_id: &str) {
+    let forwards: Vec<(String,)> =
+        sqlx::query_as("SELECT id FROM active_forwards WHERE favorite_id = ?")
+            .bind(favorite_id)
+            .fetch_all(&state.db)
+            .await
+            .unwrap_or_default();
+    for (id,) in forwards {
+        if let Err(e) = kill_forward(id, state.clone()).await {
+            log::warn!("Failed to stop forward for discarded favorite: {e}");
+        }
+    }
+
+    let connections: Vec<(String,)> =
+        sqlx::query_as("SELECT id FROM pg_connections WHERE favorite_id = ?")
+            .bind(favorite_id)
+            .fetch_all(&state.db)
+            .await
+            .unwrap_or_default();
+    for (id,) in connections {
+        if let Err(e) = pg_delete_connection(id, state.clone()).await {
+            log::warn!("Failed to delete connection for discarded favorite: {e}");
+        }
+    }
+
+    if let Err(e) = delete_favorite(favorite_id.to_string(), state.clone()).await {
+        log::warn!("Failed to delete discarded favorite: {e}");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Turn detected Postgres credentials into a working connection in one step.
+///
+/// `detected_source` is the `source` of a `detect_db_credentials` result.
+/// The detected host is matched to a Service, a favorite and forward are
+/// reused or created for it, and a `pg_connections` row pointing at
+/// `127.0.0.1:<local_port>` is saved (updated when one already exists for
+/// the same favorite, database and user) and connected. If a later step
+/// fails, a favorite created here is removed again with its forward and
+/// connection, and a forward started here for an existing favorite is
+/// stopped.
+#[tauri::command]
+pub async fn provision_db_access(
+    kubeconfig_id: String,
+    namespace: String,
+    detected_source: String,
+    state: State<'_, AppState>,
+    vault_state: State<'_, VaultState>,
+) -> Result<ProvisionedDbAccess, String> {
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    let detected = detect_credentials(client, &namespace).await?;
+
+    let from_source: Vec<&DetectedCredentials> = detected
+        .iter()
+        .filter(|c| c.source == detected_source)
+        .collect();
+    let cred = match from_source.iter().find(|c| c.engine == "postgres") {
+        Some(cred) => *cred,
+        None => match from_source.first() {
+            Some(other) => {
+                return Err(format!(
+                    "Only Postgres credentials can be provisioned; {detected_source} holds {} credentials",
+                    other.engine
+                ))
+            }
+            None => return Err(format!("No credentials detected in {detected_source}")),
+        },
+    };
+
+    let target = match_service(cred)?;
+    let database = cred.database.as_deref().unwrap_or(DEFAULT_DATABASE);
+    let username = cred.username.as_deref().unwrap_or(DEFAULT_USERNAME);
+    let label = format!("{}/{}", target.service_name, database);
+
+    let (favorite_id, created_favorite) =
+        ensure_favorite(&state.db, &kubeconfig_id, &namespace, target, &label).await?;
+
+    // A forward this call started, to stop again if a later step fails
+    let mut started_forward: Option<String> = None;
+    let provisioned: Result<ProvisionedDbAccess, String> = async {
+        let forward = match find_running_forward(&state.db, None, Some(&favorite_id)).await? {
+            Some(forward) => forward,
+            None => {
+                let forward = create_forward(
+                    kubeconfig_id.clone(),
+                    namespace.clone(),
+                    "service".to_string(),
+                    target.service_name.clone(),
+                    target.port as i64,
+                    None,
+                    Some(favorite_id.clone()),
+                    state.clone(),
+                )
+                .await?;
+                started_forward = Some(forward.id.clone());
+                forward
+            }
+        };
+        if !wait_for_local_port(forward.local_port as u16, FORWARD_READY_TIMEOUT).await {
+            return Err(format!(
+                "Port forward to service/{} did not start listening on port {}",
+                target.service_name, forward.local_port
+            ));
+        }
+
+        let existing: Option<(String,)> = sqlx::query_as(
+            "SELECT id FROM pg_connections WHERE favorite_id = ? AND database_name = ? AND username = ?",
+        )
+        .bind(&favorite_id)
+        .bind(database)
+        .bind(username)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let connection = pg_save_connection(
+            existing.map(|(id,)| id),
+            Some(label),
+            Some(forward.id.clone()),
+            Some(favorite_id.clone()),
+            "127.0.0.1".to_string(),
+            forward.local_port,
+            database.to_string(),
+            username.to_string(),
+            cred.password.clone(),
+            cred.ssl_mode
+                .clone()
+                .unwrap_or_else(|| DEFAULT_SSL_MODE.to_string()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            state.clone(),
+        )
+        .await?;
+
+        let message = pg_connect(connection.id.clone(), state.clone(), vault_state).await?;
+
+        Ok(ProvisionedDbAccess {
+            connection,
+            forward,
+            favorite_id: favorite_id.clone(),
+            message,
+        })
+    }
+    .await;
+
+    if provisioned.is_err() {
+        if created_favorite {
+            discard_favorite(&state, &favorite_id).await;
+        } else if let Some(id) = started_forward {
+            if let Err(e) = kill_forward(id, state.clone()).await {
+                log::warn!("Failed to stop forward after failed provisioning: {e}");
+            }
+        }
+    }
+    provisioned
+}
@@ -1,10 +1,16 @@
 use serde::Serialize;
 use sqlx::sqlite::SqlitePool;
+use std::io::Read;
 use std::os::unix::fs::PermissionsExt;
-use std::process::{Command, Stdio};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, Command, Stdio};
 use tauri::State;
 
-use crate::kubeconfig::get_kubeconfig_content;
+use crate::k8s::{
+    build_client_untracked, fetch_events, find_target_pod, require_access, resolve_target_pod,
+};
+use crate::kubeconfig::{get_kubeconfig_content, touch_kubeconfig};
+use crate::pgmanager::rebind_connections;
 use crate::AppState;
 
 #[derive(Debug, Serialize, sqlx::FromRow, Clone)]
@@ -15,6 +21,8 @@ pub struct ActiveForward {
     pub namespace: String,
     pub resource_type: String,
     pub resource_name: String,
+    /// Pod a `deployment`, `statefulset` or `selector` target resolved to.
+    pub target_pod: Option<String>,
     pub remote_port: i64,
     pub local_port: i64,
     pub pid: Option<i64>,
@@ -23,6 +31,12 @@ pub struct ActiveForward {
     pub error_msg: Option<String>,
 }
 
+/// Resource types that are resolved to a single ready pod when the forward starts.
+const WORKLOAD_RESOURCE_TYPES: &[&str] = &["deployment", "statefulset", "selector"];
+
+/// Number of recent Warning events appended to a dropped forward's error message.
+const DROP_WARNING_LIMIT: usize = 5;
+
 #[tauri::command]
 pub async fn create_forward(
     kubeconfig_id: String,
@@ -34,6 +48,18 @@ pub async fn create_forward(
     favorite_id: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<ActiveForward, String> {
+    if resource_type != "service"
+        && resource_type != "pod"
+        && !WORKLOAD_RESOURCE_TYPES.contains(&resource_type.as_str())
+    {
+        return Err(format!("Unsupported resource type '{}'", resource_type));
+    }
+
+    // kubectl only reports a generic failure on stderr, so surface missing
+    // RBAC permissions up front.
+    let client = build_client_untracked(&kubeconfig_id, &state.db).await?;
+    require_access(client, &namespace, "create", "pods/portforward").await?;
+
     // Determine local port: use the provided one or find a free one
     let local = local_port.unwrap_or_else(|| find_free_port(&state.db));
 
@@ -42,6 +68,23 @@ pub async fn create_forward(
         return Err(format!("Port {} is already in use", local));
     }
 
+    // Workloads and selectors are pinned to a ready pod now, so a restart
+    // re-resolves and survives pod name churn.
+    let target_pod = if WORKLOAD_RESOURCE_TYPES.contains(&resource_type.as_str()) {
+        Some(
+            resolve_target_pod(
+                &kubeconfig_id,
+                &namespace,
+                &resource_type,
+                &resource_name,
+                &state.db,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
     // Write kubeconfig to a temp file for kubectl to consume
     let content = get_kubeconfig_content(&kubeconfig_id, &state.db).await?;
     let tmp_dir = std::env::temp_dir().join("port-manager");
@@ -52,7 +95,10 @@ pub async fn create_forward(
         .map_err(|e| format!("Failed to set permissions on temp kubeconfig: {e}"))?;
 
     // Build and spawn the kubectl port-forward command
-    let resource = format!("{}/{}", resource_type, resource_name);
+    let resource = match &target_pod {
+        Some(pod) => format!("pod/{}", pod),
+        None => format!("{}/{}", resource_type, resource_name),
+    };
     let port_mapping = format!("{}:{}", local, remote_port);
 
     let child = Command::new("kubectl")
@@ -73,7 +119,7 @@ pub async fn create_forward(
 
     // Persist the forward record in the database
     sqlx::query(
-        "INSERT INTO active_forwards (id, favorite_id, kubeconfig_id, namespace, resource_type, resource_name, remote_port, local_port, pid, status, started_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, 'running', ?)"
+        "INSERT INTO active_forwards (id, favorite_id, kubeconfig_id, namespace, resource_type, resource_name, target_pod, remote_port, local_port, pid, status, started_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'running', ?)"
     )
     .bind(&id)
     .bind(&favorite_id)
@@ -81,6 +127,7 @@ pub async fn create_forward(
     .bind(&namespace)
     .bind(&resource_type)
     .bind(&resource_name)
+    .bind(&target_pod)
     .bind(remote_port)
     .bind(local)
     .bind(pid)
@@ -89,20 +136,38 @@ pub async fn create_forward(
     .await
     .map_err(|e| e.to_string())?;
 
-    Ok(ActiveForward {
+    touch_kubeconfig(&kubeconfig_id, &state.db).await;
+
+    // Watch for kubectl dying so the forward is flagged with the reason
+    tokio::spawn(monitor_forward(
+        child,
+        state.db.clone(),
+        id.clone(),
+        kubeconfig_id.clone(),
+        namespace.clone(),
+        resource.clone(),
+    ));
+
+    let forward = ActiveForward {
         id,
         favorite_id,
         kubeconfig_id,
         namespace,
         resource_type,
         resource_name,
+        target_pod,
         remote_port,
         local_port: local,
         pid: Some(pid),
         status: "running".to_string(),
         started_at: Some(now),
         error_msg: None,
-    })
+    };
+
+    // Saved Postgres connections of this favorite now live on the new port
+    rebind_connections(&state, &forward).await;
+
+    Ok(forward)
 }
 
 #[tauri::command]
@@ -136,7 +201,7 @@ pub async fn kill_forward(id: String, state: State<'_, AppState>) -> Result<(),
 pub async fn restart_forward(id: String, state: State<'_, AppState>) -> Result<ActiveForward, String> {
     // Fetch the existing forward details
     let forward: ActiveForward = sqlx::query_as(
-        "SELECT id, favorite_id, kubeconfig_id, namespace, resource_type, resource_name, remote_port, local_port, pid, status, started_at, error_msg FROM active_forwards WHERE id = ?"
+        "SELECT id, favorite_id, kubeconfig_id, namespace, resource_type, resource_name, target_pod, remote_port, local_port, pid, status, started_at, error_msg FROM active_forwards WHERE id = ?"
     )
     .bind(&id)
     .fetch_one(&state.db)
@@ -150,15 +215,8 @@ pub async fn restart_forward(id: String, state: State<'_, AppState>) -> Result<A
         }
     }
 
-    // Remove the old record
-    sqlx::query("DELETE FROM active_forwards WHERE id = ?")
-        .bind(&id)
-        .execute(&state.db)
-        .await
-        .map_err(|e| e.to_string())?;
-
     // Create a fresh forward with the same parameters
-    create_forward(
+    let restarted = match create_forward(
         forward.kubeconfig_id,
         forward.namespace,
         forward.resource_type,
@@ -166,15 +224,56 @@ pub async fn restart_forward(id: String, state: State<'_, AppState>) -> Result<A
         forward.remote_port,
         Some(forward.local_port),
         forward.favorite_id,
-        state,
+        state.clone(),
     )
     .await
+    {
+        Ok(restarted) => restarted,
+        Err(e) => {
+            // Keep the old record, stopped, so its connections stay bound
+            let _ = sqlx::query(
+                "UPDATE active_forwards SET status = 'stopped', pid = NULL WHERE id = ?",
+            )
+            .bind(&id)
+            .execute(&state.db)
+            .await;
+            return Err(e);
+        }
+    };
+
+    // Connections bound to the old forward ID follow it to the new one
+    retire_forward(&state.db, &id, &restarted.id).await?;
+    rebind_connections(&state, &restarted).await;
+
+    Ok(restarted)
+}
+
+/// Move the saved connections bound to forward `old_id` over to `new_id`
+/// and delete the old record, in one transaction. Deleting it first would
+/// unbind them through `pg_connections.forward_id ... ON DELETE SET NULL`.
+async fn retire_forward(pool: &SqlitePool, old_id: &str, new_id: &str) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::query("UPDATE pg_connections SET forward_id = ? WHERE forward_id = ?")
+        .bind(new_id)
+        .bind(old_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to move connections to the restarted forward: {e}"))?;
+
+    sqlx::query("DELETE FROM active_forwards WHERE id = ?")
+        .bind(old_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn list_forwards(state: State<'_, AppState>) -> Result<Vec<ActiveForward>, String> {
     let forwards: Vec<ActiveForward> = sqlx::query_as(
-        "SELECT id, favorite_id, kubeconfig_id, namespace, resource_type, resource_name, remote_port, local_port, pid, status, started_at, error_msg FROM active_forwards ORDER BY started_at DESC"
+        "SELECT id, favorite_id, kubeconfig_id, namespace, resource_type, resource_name, target_pod, remote_port, local_port, pid, status, started_at, error_msg FROM active_forwards ORDER BY started_at DESC"
     )
     .fetch_all(&state.db)
     .await
@@ -183,13 +282,87 @@ pub async fn list_forwards(state: State<'_, AppState>) -> Result<Vec<ActiveForwa
     Ok(forwards)
 }
 
+/// Wait for a kubectl port-forward process to exit and, unless it was stopped
+/// on purpose, mark the forward as failed with kubectl's stderr and the
+/// target's recent Warning events (CrashLoopBackOff, eviction, ...).
+///
+/// `resource` is kubectl's target. Crash loops and probe failures are
+/// reported on pods, so for a `service/<name>` target the warnings come from
+/// a pod behind it, looked up only once the forward has failed.
+async fn monitor_forward(
+    mut child: Child,
+    pool: SqlitePool,
+    forward_id: String,
+    kubeconfig_id: String,
+    namespace: String,
+    resource: String,
+) {
+    let waited = tokio::task::spawn_blocking(move || {
+        let mut stderr = String::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr);
+        }
+        child.wait().map(|status| (status, stderr))
+    })
+    .await;
+
+    let (status, stderr) = match waited {
+        Ok(Ok(result)) => result,
+        _ => return,
+    };
+
+    // kill_forward and restart_forward stop kubectl with SIGTERM
+    if status.signal() == Some(libc::SIGTERM) {
+        return;
+    }
+
+    let mut error_msg = stderr.trim().to_string();
+    if error_msg.is_empty() {
+        error_msg = format!("kubectl port-forward exited ({})", status);
+    }
+
+    let warnings = match build_client_untracked(&kubeconfig_id, &pool).await {
+        Ok(client) => {
+            let service_pod = match resource.strip_prefix("service/") {
+                Some(service) => find_target_pod(client.clone(), &namespace, "service", service)
+                    .await
+                    .ok(),
+                None => None,
+            };
+            let involved_object = service_pod.map_or(resource, |pod| format!("pod/{}", pod));
+            fetch_events(client, &namespace, Some(&involved_object), true)
+                .await
+                .unwrap_or_default()
+        }
+        Err(_) => Vec::new(),
+    };
+    if !warnings.is_empty() {
+        error_msg.push_str("\n\nRecent warnings:");
+        for event in warnings.iter().take(DROP_WARNING_LIMIT) {
+            error_msg.push_str(&format!(
+                "\n- {}: {}",
+                event.reason.as_deref().unwrap_or("Unknown"),
+                event.message.as_deref().unwrap_or_default()
+            ));
+        }
+    }
+
+    let _ = sqlx::query(
+        "UPDATE active_forwards SET status = 'error', pid = NULL, error_msg = ? WHERE id = ? AND status = 'running'",
+    )
+    .bind(&error_msg)
+    .bind(&forward_id)
+    .execute(&pool)
+    .await;
+}
+
 /// Find forwards that were marked as "running" from a previous session (stale)
 /// and mark them as "stopped". Processes are dead after an app restart, so their
 /// PIDs are no longer valid.
 pub async fn cleanup_stale_forwards(pool: &SqlitePool) -> Result<Vec<ActiveForward>, String> {
     // Find forwards that were "running" (stale from previous session)
     let stale: Vec<ActiveForward> = sqlx::query_as(
-        "SELECT id, favorite_id, kubeconfig_id, namespace, resource_type, resource_name, remote_port, local_port, pid, status, started_at, error_msg FROM active_forwards WHERE status = 'running'"
+        "SELECT id, favorite_id, kubeconfig_id, namespace, resource_type, resource_name, target_pod, remote_port, local_port, pid, status, started_at, error_msg FROM active_forwards WHERE status = 'running'"
     )
     .fetch_all(pool)
     .await
@@ -204,6 +377,42 @@ pub async fn cleanup_stale_forwards(pool: &SqlitePool) -> Result<Vec<ActiveForwa
     Ok(stale)
 }
 
+/// The running forward with id `forward_id`, or else the most recent running
+/// forward started from `favorite_id`.
+pub async fn find_running_forward(
+    pool: &SqlitePool,
+    forward_id: Option<&str>,
+    favorite_id: Option<&str>,
+) -> Result<Option<ActiveForward>, String> {
+    sqlx::query_as(
+        "SELECT id, favorite_id, kubeconfig_id, namespace, resource_type, resource_name, target_pod, remote_port, local_port, pid, status, started_at, error_msg FROM active_forwards \
+         WHERE status = 'running' AND (id = ? OR (favorite_id IS NOT NULL AND favorite_id = ?)) \
+         ORDER BY id = ? DESC, started_at DESC LIMIT 1",
+    )
+    .bind(forward_id)
+    .bind(favorite_id)
+    .bind(forward_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Wait until something accepts connections on a local port, e.g. a freshly
+/// spawned kubectl port-forward. Returns `false` if it never does within
+/// `timeout`.
+pub async fn wait_for_local_port(port: u16, timeout: std::time::Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if tokio::net::TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+}
+
 /// Check whether a given TCP port is already bound on localhost.
 fn is_port_in_use(port: u16) -> bool {
     std::net::TcpListener::bind(("127.0.0.1", port)).is_err()
@@ -225,3 +434,62 @@ fn find_free_port(_pool: &SqlitePool) -> i64 {
     }
     8080
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_db() -> (SqlitePool, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("port-manager-test-{}", uuid::Uuid::new_v4()));
+        (crate::db::init_db(dir.clone()).await.unwrap(), dir)
+    }
+
+    async fn insert_forward(pool: &SqlitePool, id: &str, local_port: i64) {
+        sqlx::query(
+            "INSERT INTO active_forwards (id, kubeconfig_id, namespace, resource_type, resource_name, remote_port, local_port, status) \
+             VALUES (?, 'kc', 'default', 'service', 'db', 5432, ?, 'running')",
+        )
+        .bind(id)
+        .bind(local_port)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn restart_keeps_forward_only_connections_bound() {
+        let (pool, dir) = test_db().await;
+        sqlx::query("INSERT INTO kubeconfigs (id, name, content) VALUES ('kc', 'test', x'')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        insert_forward(&pool, "old", 3001).await;
+        // Bound through the forward only, with no favorite
+        sqlx::query(
+            "INSERT INTO pg_connections (id, forward_id, port) VALUES ('conn', 'old', 3001)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        insert_forward(&pool, "new", 3002).await;
+        retire_forward(&pool, "old", "new").await.unwrap();
+
+        let (forward_id,): (Option<String>,) =
+            sqlx::query_as("SELECT forward_id FROM pg_connections WHERE id = 'conn'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(forward_id.as_deref(), Some("new"));
+
+        let (old_rows,): (i64,) =
+            sqlx::query_as("SELECT count(*) FROM active_forwards WHERE id = 'old'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(old_rows, 0);
+
+        pool.close().await;
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
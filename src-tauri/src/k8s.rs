@@ -1,28 +1,36 @@
 use std::collections::HashMap;
-use std::sync::LazyLock;
+use std::time::{Duration, Instant};
 
-use k8s_openapi::api::core::v1::{Namespace, Pod, Secret, Service};
+use k8s_openapi::api::apps::v1::{Deployment, StatefulSet};
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes, SelfSubjectAccessReview, SelfSubjectAccessReviewSpec,
+};
+use k8s_openapi::api::core::v1::{
+    ContainerState, ContainerStatus, Event, Namespace, Pod, Service,
+};
+use k8s_openapi::api::discovery::v1::EndpointSlice;
+use k8s_openapi::api::networking::v1::{Ingress, IngressBackend};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
 use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
 use kube::{
-    api::ListParams,
+    api::{ListParams, PostParams},
     config::{KubeConfigOptions, Kubeconfig},
     Api, Client, Config,
 };
-use regex::Regex;
 use serde::Serialize;
 use tauri::State;
 
-use crate::kubeconfig::get_kubeconfig_content;
+use crate::kubeconfig::{get_kubeconfig_content, touch_kubeconfig};
 use crate::AppState;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
 pub struct K8sService {
     pub name: String,
     pub namespace: String,
     pub ports: Vec<ServicePort>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
 pub struct ServicePort {
     pub name: Option<String>,
     pub port: i32,
@@ -30,15 +38,43 @@ pub struct ServicePort {
     pub protocol: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
 pub struct K8sPod {
     pub name: String,
     pub namespace: String,
     pub status: String,
     pub ports: Vec<PodPort>,
+    /// `true` when the pod's `Ready` condition is `True`.
+    pub ready: bool,
+    pub restart_count: i32,
+    pub containers: Vec<PodContainer>,
+    pub node_name: Option<String>,
+    pub pod_ip: Option<String>,
+    pub owner: Option<PodOwner>,
+    pub created_at: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone)]
+pub struct PodContainer {
+    pub name: String,
+    pub image: Option<String>,
+    pub ready: bool,
+    pub restart_count: i32,
+    /// `running`, `waiting`, `terminated` or `unknown`.
+    pub state: String,
+    /// Reason attached to the current state, e.g. `CrashLoopBackOff`.
+    pub state_reason: Option<String>,
+    pub last_termination_reason: Option<String>,
+    pub last_exit_code: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PodOwner {
+    pub kind: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
 pub struct PodPort {
     pub name: Option<String>,
     pub container_port: i32,
@@ -53,7 +89,187 @@ fn format_int_or_string(ios: &IntOrString) -> String {
     }
 }
 
-async fn build_client(
+/// Convert a Kubernetes Service into the model sent to the frontend.
+pub fn service_to_model(svc: Service) -> K8sService {
+    let name = svc.metadata.name.unwrap_or_default();
+    let ns = svc.metadata.namespace.unwrap_or_default();
+    let ports = svc
+        .spec
+        .as_ref()
+        .and_then(|s| s.ports.as_ref())
+        .map(|ports| {
+            ports
+                .iter()
+                .map(|p| ServicePort {
+                    name: p.name.clone(),
+                    port: p.port,
+                    target_port: p.target_port.as_ref().map(format_int_or_string),
+                    protocol: p.protocol.clone().unwrap_or_else(|| "TCP".to_string()),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    K8sService {
+        name,
+        namespace: ns,
+        ports,
+    }
+}
+
+/// Convert a Kubernetes Pod into the model sent to the frontend.
+pub fn pod_to_model(pod: Pod) -> K8sPod {
+    let owner = pod_owner(&pod);
+    let created_at = pod
+        .metadata
+        .creation_timestamp
+        .as_ref()
+        .map(|t| t.0.to_rfc3339());
+    let name = pod.metadata.name.unwrap_or_default();
+    let ns = pod.metadata.namespace.unwrap_or_default();
+    let status = pod
+        .status
+        .as_ref()
+        .and_then(|s| s.phase.clone())
+        .unwrap_or_else(|| "Unknown".to_string());
+    let ports = pod
+        .spec
+        .as_ref()
+        .map(|spec| {
+            spec.containers
+                .iter()
+                .flat_map(|c| {
+                    c.ports
+                        .as_ref()
+                        .map(|ports| {
+                            ports
+                                .iter()
+                                .map(|p| PodPort {
+                                    name: p.name.clone(),
+                                    container_port: p.container_port,
+                                    protocol: p
+                                        .protocol
+                                        .clone()
+                                        .unwrap_or_else(|| "TCP".to_string()),
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let pod_status = pod.status.as_ref();
+    let ready = pod_status
+        .and_then(|s| s.conditions.as_ref())
+        .map(|conds| conds.iter().any(|c| c.type_ == "Ready" && c.status == "True"))
+        .unwrap_or(false);
+    let containers: Vec<PodContainer> = pod
+        .spec
+        .as_ref()
+        .map(|spec| {
+            spec.containers
+                .iter()
+                .map(|c| {
+                    let container_status = pod_status
+                        .and_then(|s| s.container_statuses.as_ref())
+                        .and_then(|statuses| statuses.iter().find(|cs| cs.name == c.name));
+                    container_to_model(&c.name, c.image.clone(), container_status)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let restart_count = containers.iter().map(|c| c.restart_count).sum();
+
+    K8sPod {
+        name,
+        namespace: ns,
+        status,
+        ports,
+        ready,
+        restart_count,
+        containers,
+        node_name: pod.spec.as_ref().and_then(|s| s.node_name.clone()),
+        pod_ip: pod_status.and_then(|s| s.pod_ip.clone()),
+        owner,
+        created_at,
+    }
+}
+
+/// Merge a container spec with its runtime status (if the kubelet reported one).
+fn container_to_model(
+    name: &str,
+    image: Option<String>,
+    status: Option<&ContainerStatus>,
+) -> PodContainer {
+    let current = status.and_then(|s| s.state.as_ref());
+    let (state, state_reason) = match current {
+        Some(ContainerState { running: Some(_), .. }) => ("running", None),
+        Some(ContainerState { waiting: Some(w), .. }) => ("waiting", w.reason.clone()),
+        Some(ContainerState { terminated: Some(t), .. }) => ("terminated", t.reason.clone()),
+        _ => ("unknown", None),
+    };
+    let last_terminated = status
+        .and_then(|s| s.last_state.as_ref())
+        .and_then(|s| s.terminated.as_ref());
+
+    PodContainer {
+        name: name.to_string(),
+        image: status.map(|s| s.image.clone()).or(image),
+        ready: status.map(|s| s.ready).unwrap_or(false),
+        restart_count: status.map(|s| s.restart_count).unwrap_or(0),
+        state: state.to_string(),
+        state_reason,
+        last_termination_reason: last_terminated.and_then(|t| t.reason.clone()),
+        last_exit_code: last_terminated.map(|t| t.exit_code),
+    }
+}
+
+/// Resolve the workload that owns a pod.
+///
+/// ReplicaSets created by a Deployment are named `<deployment>-<pod-template-hash>`,
+/// so the Deployment is derived from the pod's `pod-template-hash` label without
+/// an extra API call.
+fn pod_owner(pod: &Pod) -> Option<PodOwner> {
+    let owner = pod
+        .metadata
+        .owner_references
+        .as_ref()?
+        .iter()
+        .find(|o| o.controller.unwrap_or(false))?;
+
+    if owner.kind == "ReplicaSet" {
+        let hash = pod
+            .metadata
+            .labels
+            .as_ref()
+            .and_then(|l| l.get("pod-template-hash"));
+        if let Some(deployment) = hash.and_then(|h| owner.name.strip_suffix(&format!("-{h}"))) {
+            return Some(PodOwner {
+                kind: "Deployment".to_string(),
+                name: deployment.to_string(),
+            });
+        }
+    }
+
+    Some(PodOwner {
+        kind: owner.kind.clone(),
+        name: owner.name.clone(),
+    })
+}
+
+/// Build a client for a stored kubeconfig and record it as used.
+pub async fn build_client(
+    kubeconfig_id: &str,
+    pool: &sqlx::sqlite::SqlitePool,
+) -> Result<Client, String> {
+    let client = build_client_untracked(kubeconfig_id, pool).await?;
+    touch_kubeconfig(kubeconfig_id, pool).await;
+    Ok(client)
+}
+
+/// Build a client without touching `last_used`, for background fan-outs that
+/// the user did not direct at this particular cluster.
+pub async fn build_client_untracked(
     kubeconfig_id: &str,
     pool: &sqlx::sqlite::SqlitePool,
 ) -> Result<Client, String> {
@@ -66,6 +282,102 @@ async fn build_client(
     Client::try_from(config).map_err(|e| format!("Failed to create K8s client: {}", e))
 }
 
+// ---------------------------------------------------------------------------
+// Cluster health
+// ---------------------------------------------------------------------------
+
+/// Upper bound for each API server round-trip made by `check_cluster`.
+const CLUSTER_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ClusterStatus {
+    pub kubeconfig_id: String,
+    pub reachable: bool,
+    pub auth_valid: bool,
+    pub server_version: Option<String>,
+    pub latency_ms: Option<i64>,
+    pub error_msg: Option<String>,
+    pub checked_at: String,
+}
+
+/// Probe the API server behind a kubeconfig and persist the result.
+///
+/// Reachability and latency come from the unauthenticated `/version`
+/// endpoint; auth validity from a one-item namespace list, where only a 401
+/// counts as invalid credentials (a 403 still proves the identity is valid).
+#[tauri::command]
+pub async fn check_cluster(
+    kubeconfig_id: String,
+    state: State<'_, AppState>,
+) -> Result<ClusterStatus, String> {
+    let mut status = ClusterStatus {
+        kubeconfig_id: kubeconfig_id.clone(),
+        reachable: false,
+        auth_valid: false,
+        server_version: None,
+        latency_ms: None,
+        error_msg: None,
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    match build_client(&kubeconfig_id, &state.db).await {
+        Ok(client) => {
+            let start = Instant::now();
+            match tokio::time::timeout(CLUSTER_CHECK_TIMEOUT, client.apiserver_version()).await {
+                Ok(Ok(info)) => {
+                    status.reachable = true;
+                    status.latency_ms = Some(start.elapsed().as_millis() as i64);
+                    status.server_version = Some(info.git_version);
+                }
+                Ok(Err(e)) => status.error_msg = Some(format!("API server unreachable: {e}")),
+                Err(_) => status.error_msg = Some("API server did not respond in time".to_string()),
+            }
+
+            if status.reachable {
+                let namespaces: Api<Namespace> = Api::all(client);
+                let params = ListParams::default().limit(1);
+                match tokio::time::timeout(CLUSTER_CHECK_TIMEOUT, namespaces.list(&params)).await {
+                    Ok(Ok(_)) => status.auth_valid = true,
+                    Ok(Err(kube::Error::Api(ae))) if ae.code == 403 => status.auth_valid = true,
+                    Ok(Err(e)) => status.error_msg = Some(format!("Authentication failed: {e}")),
+                    Err(_) => status.error_msg = Some("Auth check timed out".to_string()),
+                }
+            }
+        }
+        Err(e) => status.error_msg = Some(e),
+    }
+
+    sqlx::query(
+        "INSERT INTO cluster_status (kubeconfig_id, reachable, auth_valid, server_version, latency_ms, error_msg, checked_at) VALUES (?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(kubeconfig_id) DO UPDATE SET reachable = excluded.reachable, auth_valid = excluded.auth_valid, server_version = excluded.server_version, latency_ms = excluded.latency_ms, error_msg = excluded.error_msg, checked_at = excluded.checked_at"
+    )
+    .bind(&status.kubeconfig_id)
+    .bind(status.reachable)
+    .bind(status.auth_valid)
+    .bind(&status.server_version)
+    .bind(status.latency_ms)
+    .bind(&status.error_msg)
+    .bind(&status.checked_at)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Failed to record cluster status: {e}"))?;
+
+    Ok(status)
+}
+
+/// Last recorded health check for every kubeconfig that has been checked.
+#[tauri::command]
+pub async fn list_cluster_statuses(
+    state: State<'_, AppState>,
+) -> Result<Vec<ClusterStatus>, String> {
+    sqlx::query_as::<_, ClusterStatus>(
+        "SELECT kubeconfig_id, reachable, auth_valid, server_version, latency_ms, error_msg, checked_at FROM cluster_status",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| format!("Failed to list cluster statuses: {e}"))
+}
+
 #[tauri::command]
 pub async fn list_namespaces(
     kubeconfig_id: String,
@@ -98,35 +410,7 @@ pub async fn list_services(
         .await
         .map_err(|e| e.to_string())?;
 
-    Ok(list
-        .items
-        .into_iter()
-        .map(|svc| {
-            let name = svc.metadata.name.unwrap_or_default();
-            let ns = svc.metadata.namespace.unwrap_or_default();
-            let ports = svc
-                .spec
-                .as_ref()
-                .and_then(|s| s.ports.as_ref())
-                .map(|ports| {
-                    ports
-                        .iter()
-                        .map(|p| ServicePort {
-                            name: p.name.clone(),
-                            port: p.port,
-                            target_port: p.target_port.as_ref().map(format_int_or_string),
-                            protocol: p.protocol.clone().unwrap_or_else(|| "TCP".to_string()),
-                        })
-                        .collect()
-                })
-                .unwrap_or_default();
-            K8sService {
-                name,
-                namespace: ns,
-                ports,
-            }
-        })
-        .collect())
+    Ok(list.items.into_iter().map(service_to_model).collect())
 }
 
 #[tauri::command]
@@ -142,353 +426,617 @@ pub async fn list_pods(
         .await
         .map_err(|e| e.to_string())?;
 
-    Ok(list
-        .items
-        .into_iter()
-        .map(|pod| {
-            let name = pod.metadata.name.unwrap_or_default();
-            let ns = pod.metadata.namespace.unwrap_or_default();
-            let status = pod
-                .status
-                .as_ref()
-                .and_then(|s| s.phase.clone())
-                .unwrap_or_else(|| "Unknown".to_string());
-            let ports = pod
-                .spec
-                .as_ref()
-                .map(|spec| {
-                    spec.containers
-                        .iter()
-                        .flat_map(|c| {
-                            c.ports
-                                .as_ref()
-                                .map(|ports| {
-                                    ports
-                                        .iter()
-                                        .map(|p| PodPort {
-                                            name: p.name.clone(),
-                                            container_port: p.container_port,
-                                            protocol: p
-                                                .protocol
-                                                .clone()
-                                                .unwrap_or_else(|| "TCP".to_string()),
-                                        })
-                                        .collect::<Vec<_>>()
-                                })
-                                .unwrap_or_default()
-                        })
-                        .collect()
-                })
-                .unwrap_or_default();
-            K8sPod {
-                name,
-                namespace: ns,
-                status,
-                ports,
-            }
-        })
-        .collect())
+    Ok(list.items.into_iter().map(pod_to_model).collect())
 }
 
 // ---------------------------------------------------------------------------
-// Database credential detection
+// Ingresses and endpoints
 // ---------------------------------------------------------------------------
 
 #[derive(Debug, Serialize, Clone)]
-pub struct DetectedCredentials {
-    pub source: String,
+pub struct K8sIngress {
+    pub name: String,
+    pub namespace: String,
+    pub class_name: Option<String>,
+    pub routes: Vec<IngressRoute>,
+}
+
+/// One host/path of an Ingress and the Service it routes to.
+#[derive(Debug, Serialize, Clone)]
+pub struct IngressRoute {
     pub host: Option<String>,
-    pub port: Option<u16>,
-    pub database: Option<String>,
-    pub username: Option<String>,
-    pub password: Option<String>,
-    pub ssl_mode: Option<String>,
-    pub confidence: f32,
-}
-
-/// Well-known environment / secret key names grouped by database field.
-const PASSWORD_KEYS: &[&str] = &[
-    "POSTGRES_PASSWORD",
-    "PGPASSWORD",
-    "DB_PASSWORD",
-    "DATABASE_PASSWORD",
-    "password",
-];
-const USER_KEYS: &[&str] = &[
-    "POSTGRES_USER",
-    "PGUSER",
-    "DB_USER",
-    "DATABASE_USER",
-    "username",
-];
-const DATABASE_KEYS: &[&str] = &[
-    "POSTGRES_DB",
-    "PGDATABASE",
-    "DB_NAME",
-    "DATABASE_NAME",
-    "database",
-];
-const HOST_KEYS: &[&str] = &["DB_HOST", "DATABASE_HOST", "PGHOST", "host"];
-const PORT_KEYS: &[&str] = &["DB_PORT", "DATABASE_PORT", "PGPORT", "port"];
-const URL_KEYS: &[&str] = &["DATABASE_URL", "POSTGRES_URL", "PG_URL", "DB_URL"];
-const SSL_KEYS: &[&str] = &["PGSSLMODE", "DB_SSLMODE", "SSL_MODE"];
-
-static DATABASE_URL_RE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"postgres(?:ql)?://([^:]+):(.+)@([^@:/]+)(?::(\d+))?/([^\s?]+)")
-        .expect("invalid regex")
-});
-
-/// Intermediate mutable builder used while scanning keys.
-#[derive(Default, Clone)]
-struct CredBuilder {
-    host: Option<String>,
-    port: Option<u16>,
-    database: Option<String>,
-    username: Option<String>,
-    password: Option<String>,
-    ssl_mode: Option<String>,
-}
-
-impl CredBuilder {
-    /// Try to match a key/value pair against all known DB key names.
-    /// First-write-wins: a field is only set if it is still `None`.
-    fn ingest(&mut self, key: &str, value: &str) {
-        if PASSWORD_KEYS.contains(&key) && self.password.is_none() {
-            self.password = Some(value.to_string());
-        } else if USER_KEYS.contains(&key) && self.username.is_none() {
-            self.username = Some(value.to_string());
-        } else if DATABASE_KEYS.contains(&key) && self.database.is_none() {
-            self.database = Some(value.to_string());
-        } else if HOST_KEYS.contains(&key) && self.host.is_none() {
-            self.host = Some(value.to_string());
-        } else if PORT_KEYS.contains(&key) && self.port.is_none() {
-            self.port = value.parse().ok();
-        } else if SSL_KEYS.contains(&key) && self.ssl_mode.is_none() {
-            self.ssl_mode = Some(value.to_string());
+    pub path: String,
+    pub path_type: String,
+    pub service_name: Option<String>,
+    /// Service port number or name.
+    pub service_port: Option<String>,
+    pub tls: bool,
+    /// Public URL, only when the route has a host.
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct K8sServiceEndpoints {
+    pub service_name: String,
+    pub namespace: String,
+    pub ready: Vec<EndpointAddress>,
+    pub not_ready: Vec<EndpointAddress>,
+    pub ports: Vec<EndpointPort>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EndpointAddress {
+    pub ip: String,
+    pub pod_name: Option<String>,
+    pub node_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct EndpointPort {
+    pub name: Option<String>,
+    pub port: i32,
+    pub protocol: String,
+}
+
+/// Label EndpointSlices carry to name the Service they belong to.
+const SERVICE_NAME_LABEL: &str = "kubernetes.io/service-name";
+
+/// Service name and port (number or name) an Ingress backend points at.
+fn ingress_backend_service(backend: &IngressBackend) -> (Option<String>, Option<String>) {
+    let service = backend.service.as_ref();
+    let name = service.map(|s| s.name.clone());
+    let port = service
+        .and_then(|s| s.port.as_ref())
+        .and_then(|p| p.number.map(|n| n.to_string()).or_else(|| p.name.clone()));
+    (name, port)
+}
+
+fn ingress_to_model(ingress: Ingress) -> K8sIngress {
+    let name = ingress.metadata.name.unwrap_or_default();
+    let ns = ingress.metadata.namespace.unwrap_or_default();
+    let spec = ingress.spec.unwrap_or_default();
+
+    // A TLS entry without hosts applies to every host of the Ingress.
+    let tls = spec.tls.unwrap_or_default();
+    let is_tls = |host: Option<&str>| {
+        tls.iter().any(|t| match (&t.hosts, host) {
+            (None, _) => true,
+            (Some(hosts), Some(h)) => hosts.iter().any(|th| th == h),
+            (Some(hosts), None) => hosts.is_empty(),
+        })
+    };
+
+    let mut routes = Vec::new();
+    if let Some(backend) = &spec.default_backend {
+        let (service_name, service_port) = ingress_backend_service(backend);
+        routes.push(IngressRoute {
+            host: None,
+            path: "/".to_string(),
+            path_type: "Default".to_string(),
+            service_name,
+            service_port,
+            tls: false,
+            url: None,
+        });
+    }
+
+    for rule in spec.rules.unwrap_or_default() {
+        let host = rule.host;
+        let tls = is_tls(host.as_deref());
+        for path in rule.http.map(|h| h.paths).unwrap_or_default() {
+            let (service_name, service_port) = ingress_backend_service(&path.backend);
+            let route_path = path.path.unwrap_or_else(|| "/".to_string());
+            let url = host.as_ref().map(|h| {
+                let scheme = if tls { "https" } else { "http" };
+                format!("{scheme}://{h}{route_path}")
+            });
+            routes.push(IngressRoute {
+                host: host.clone(),
+                path: route_path,
+                path_type: path.path_type,
+                service_name,
+                service_port,
+                tls,
+                url,
+            });
         }
     }
 
-    /// Try to parse a DATABASE_URL style connection string and fill fields.
-    /// First-write-wins: a field is only set if it is still `None`.
-    fn ingest_url(&mut self, url: &str) {
-        if let Some(caps) = DATABASE_URL_RE.captures(url) {
-            if self.username.is_none() {
-                self.username = Some(caps[1].to_string());
-            }
-            if self.password.is_none() {
-                self.password = Some(caps[2].to_string());
-            }
-            if self.host.is_none() {
-                self.host = Some(caps[3].to_string());
-            }
-            if self.port.is_none() {
-                self.port = Some(
-                    caps.get(4)
-                        .and_then(|m| m.as_str().parse().ok())
-                        .unwrap_or(5432),
-                );
-            }
-            if self.database.is_none() {
-                self.database = Some(caps[5].to_string());
+    K8sIngress {
+        name,
+        namespace: ns,
+        class_name: spec.ingress_class_name,
+        routes,
+    }
+}
+
+#[tauri::command]
+pub async fn list_ingresses(
+    kubeconfig_id: String,
+    namespace: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<K8sIngress>, String> {
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    let ingresses: Api<Ingress> = Api::namespaced(client, &namespace);
+    let list = ingresses
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| format!("Failed to list ingresses: {e}"))?;
+
+    Ok(list.items.into_iter().map(ingress_to_model).collect())
+}
+
+/// Ready and not-ready endpoint addresses of every Service in `namespace`,
+/// merged from its EndpointSlices. Services without any endpoints are
+/// included with empty lists so callers can warn before forwarding.
+#[tauri::command]
+pub async fn list_endpoints(
+    kubeconfig_id: String,
+    namespace: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<K8sServiceEndpoints>, String> {
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    let services: Api<Service> = Api::namespaced(client.clone(), &namespace);
+    let slices: Api<EndpointSlice> = Api::namespaced(client, &namespace);
+
+    let service_params = ListParams::default();
+    let slice_params = ListParams::default().labels(SERVICE_NAME_LABEL);
+    let (service_list, slice_list) = tokio::join!(
+        services.list(&service_params),
+        slices.list(&slice_params),
+    );
+    let service_list = service_list.map_err(|e| format!("Failed to list services: {e}"))?;
+    let slice_list = slice_list.map_err(|e| format!("Failed to list endpoint slices: {e}"))?;
+
+    let mut by_service: HashMap<String, K8sServiceEndpoints> = service_list
+        .items
+        .into_iter()
+        .filter_map(|svc| svc.metadata.name)
+        .map(|name| {
+            let endpoints = K8sServiceEndpoints {
+                service_name: name.clone(),
+                namespace: namespace.clone(),
+                ready: Vec::new(),
+                not_ready: Vec::new(),
+                ports: Vec::new(),
+            };
+            (name, endpoints)
+        })
+        .collect();
+
+    for slice in slice_list.items {
+        let Some(service_name) = slice
+            .metadata
+            .labels
+            .as_ref()
+            .and_then(|l| l.get(SERVICE_NAME_LABEL))
+        else {
+            continue;
+        };
+        let Some(entry) = by_service.get_mut(service_name) else {
+            continue;
+        };
+
+        for port in slice.ports.unwrap_or_default() {
+            let port = EndpointPort {
+                name: port.name,
+                port: port.port.unwrap_or_default(),
+                protocol: port.protocol.unwrap_or_else(|| "TCP".to_string()),
+            };
+            if !entry
+                .ports
+                .iter()
+                .any(|p| p.name == port.name && p.port == port.port)
+            {
+                entry.ports.push(port);
             }
         }
 
-        // Extract sslmode from query string if present.
-        if self.ssl_mode.is_none() {
-            if let Some(qs_start) = url.find('?') {
-                let qs = &url[qs_start + 1..];
-                for param in qs.split('&') {
-                    if let Some(val) = param.strip_prefix("sslmode=") {
-                        self.ssl_mode = Some(val.to_string());
-                    }
+        for endpoint in slice.endpoints {
+            // An unset `ready` condition means ready, per the EndpointSlice API.
+            let ready = endpoint
+                .conditions
+                .as_ref()
+                .and_then(|c| c.ready)
+                .unwrap_or(true);
+            let pod_name = endpoint
+                .target_ref
+                .as_ref()
+                .filter(|r| r.kind.as_deref() == Some("Pod"))
+                .and_then(|r| r.name.clone());
+            for ip in endpoint.addresses {
+                let address = EndpointAddress {
+                    ip,
+                    pod_name: pod_name.clone(),
+                    node_name: endpoint.node_name.clone(),
+                };
+                if ready {
+                    entry.ready.push(address);
+                } else {
+                    entry.not_ready.push(address);
                 }
             }
         }
     }
 
-    /// Returns true when at least one DB-related field was detected.
-    fn has_any(&self) -> bool {
-        self.host.is_some()
-            || self.port.is_some()
-            || self.database.is_some()
-            || self.username.is_some()
-            || self.password.is_some()
-    }
+    let mut result: Vec<K8sServiceEndpoints> = by_service.into_values().collect();
+    result.sort_by(|a, b| a.service_name.cmp(&b.service_name));
+    Ok(result)
+}
 
-    /// Compute a confidence score: +0.25 per detected field, capped at 1.0.
-    fn confidence(&self) -> f32 {
-        let mut n: u32 = 0;
-        if self.host.is_some() {
-            n += 1;
-        }
-        if self.port.is_some() {
-            n += 1;
-        }
-        if self.database.is_some() {
-            n += 1;
-        }
-        if self.username.is_some() {
-            n += 1;
-        }
-        if self.password.is_some() {
-            n += 1;
-        }
-        (n as f32 * 0.25).min(1.0)
-    }
+// ---------------------------------------------------------------------------
+// Access checks
+// ---------------------------------------------------------------------------
 
-    fn build(self, source: String) -> DetectedCredentials {
-        let confidence = self.confidence();
-        DetectedCredentials {
-            source,
-            host: self.host,
-            port: self.port,
-            database: self.database,
-            username: self.username,
-            password: self.password,
-            ssl_mode: self.ssl_mode,
-            confidence,
-        }
+#[derive(Debug, Serialize, Clone)]
+pub struct AccessReview {
+    pub allowed: bool,
+    /// Reason given by the authorizer, if any.
+    pub reason: Option<String>,
+}
+
+/// API group of the resources the app touches. Core resources use `""`.
+fn resource_group(resource: &str) -> &'static str {
+    match resource {
+        "deployments" | "statefulsets" | "replicasets" | "daemonsets" => "apps",
+        "ingresses" => "networking.k8s.io",
+        "endpointslices" => "discovery.k8s.io",
+        "jobs" | "cronjobs" => "batch",
+        _ => "",
     }
 }
 
-/// Decode the data map of a Kubernetes Secret into plain UTF-8 strings keyed
-/// by the original key name.
-fn decode_secret_data(secret: &Secret) -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    if let Some(data) = &secret.data {
-        for (key, byte_string) in data {
-            if let Ok(value) = String::from_utf8(byte_string.0.clone()) {
-                map.insert(key.clone(), value);
+/// Ask the API server whether the current identity may perform `verb` on
+/// `resource` (e.g. `secrets` or `pods/portforward`) in `namespace`.
+pub async fn review_access(
+    client: Client,
+    namespace: &str,
+    verb: &str,
+    resource: &str,
+) -> Result<AccessReview, String> {
+    let (resource_name, subresource) = match resource.split_once('/') {
+        Some((r, sub)) => (r, Some(sub.to_string())),
+        None => (resource, None),
+    };
+
+    let review = SelfSubjectAccessReview {
+        spec: SelfSubjectAccessReviewSpec {
+            resource_attributes: Some(ResourceAttributes {
+                namespace: Some(namespace.to_string()),
+                verb: Some(verb.to_string()),
+                group: Some(resource_group(resource_name).to_string()),
+                resource: Some(resource_name.to_string()),
+                subresource,
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let reviews: Api<SelfSubjectAccessReview> = Api::all(client);
+    let result = reviews
+        .create(&PostParams::default(), &review)
+        .await
+        .map_err(|e| format!("Failed to check permissions: {e}"))?;
+
+    let status = result.status.unwrap_or_default();
+    let reason = status
+        .reason
+        .or(status.evaluation_error)
+        .filter(|r| !r.is_empty());
+    Ok(AccessReview {
+        allowed: status.allowed,
+        reason,
+    })
+}
+
+/// Fail with a specific permission error when the current identity may not
+/// perform `verb` on `resource`. If the review itself cannot be made, the
+/// check is skipped and the real request reports whatever goes wrong.
+pub async fn require_access(
+    client: Client,
+    namespace: &str,
+    verb: &str,
+    resource: &str,
+) -> Result<(), String> {
+    match review_access(client, namespace, verb, resource).await {
+        Ok(review) if review.allowed => Ok(()),
+        Ok(review) => {
+            let mut msg =
+                format!("Permission denied: cannot {verb} {resource} in namespace '{namespace}'");
+            if let Some(reason) = review.reason {
+                msg.push_str(&format!(" ({reason})"));
             }
+            Err(msg)
+        }
+        Err(e) => {
+            log::warn!("Skipping permission check for {verb} {resource}: {e}");
+            Ok(())
         }
     }
-    map
 }
 
 #[tauri::command]
-pub async fn detect_db_credentials(
+pub async fn can_i(
     kubeconfig_id: String,
     namespace: String,
+    verb: String,
+    resource: String,
     state: State<'_, AppState>,
-) -> Result<Vec<DetectedCredentials>, String> {
-    let client = build_client(&kubeconfig_id, &state.db).await?;
-
-    // ------------------------------------------------------------------
-    // 1. Fetch all secrets in the namespace and build a lookup cache.
-    // ------------------------------------------------------------------
-    let secrets_api: Api<Secret> = Api::namespaced(client.clone(), &namespace);
-    let secret_list = secrets_api
-        .list(&ListParams::default())
-        .await
-        .map_err(|e| format!("Failed to list secrets: {}", e))?;
-
-    // Cache: secret_name -> decoded key/value pairs
-    let mut secret_cache: HashMap<String, HashMap<String, String>> = HashMap::new();
-    for secret in &secret_list.items {
-        let name = secret.metadata.name.clone().unwrap_or_default();
-        let decoded = decode_secret_data(secret);
-        secret_cache.insert(name, decoded);
-    }
+) -> Result<AccessReview, String> {
+    let client = build_client_untracked(&kubeconfig_id, &state.db).await?;
+    review_access(client, &namespace, &verb, &resource).await
+}
 
-    let mut results: Vec<DetectedCredentials> = Vec::new();
+// ---------------------------------------------------------------------------
+// Forward target resolution
+// ---------------------------------------------------------------------------
 
-    // ------------------------------------------------------------------
-    // 2. Scan secrets for DB credentials.
-    // ------------------------------------------------------------------
-    for secret in &secret_list.items {
-        let secret_name = secret.metadata.name.clone().unwrap_or_default();
-        let decoded = secret_cache.get(&secret_name).cloned().unwrap_or_default();
+/// Render a `LabelSelector` in the string form accepted by `ListParams::labels`.
+/// Fails on a `matchExpressions` operator other than `In`, `NotIn`, `Exists`
+/// or `DoesNotExist`: leaving it out would widen the selector.
+fn label_selector_to_string(selector: &LabelSelector) -> Result<String, String> {
+    let mut parts: Vec<String> = Vec::new();
 
-        let mut builder = CredBuilder::default();
+    if let Some(labels) = &selector.match_labels {
+        parts.extend(labels.iter().map(|(k, v)| format!("{k}={v}")));
+    }
 
-        for (key, value) in &decoded {
-            if URL_KEYS.contains(&key.as_str()) {
-                builder.ingest_url(value);
-            } else {
-                builder.ingest(key.as_str(), value);
+    if let Some(exprs) = &selector.match_expressions {
+        for expr in exprs {
+            let values = expr.values.clone().unwrap_or_default().join(",");
+            match expr.operator.as_str() {
+                "In" => parts.push(format!("{} in ({values})", expr.key)),
+                "NotIn" => parts.push(format!("{} notin ({values})", expr.key)),
+                "Exists" => parts.push(expr.key.clone()),
+                "DoesNotExist" => parts.push(format!("!{}", expr.key)),
+                other => {
+                    return Err(format!(
+                        "Unsupported label selector operator '{other}' for key '{}'",
+                        expr.key
+                    ))
+                }
             }
         }
+    }
 
-        if builder.has_any() {
-            results.push(builder.build(format!("secret/{}", secret_name)));
+    Ok(parts.join(","))
+}
+
+/// Resolve a workload, service or label selector forward target to a ready
+/// pod name.
+///
+/// `resource_type` is one of `deployment`, `statefulset`, `service` or
+/// `selector` (where `resource_name` is the label selector itself). Pods that are terminating or
+/// not `Ready` are skipped so a forward never lands on a pod on its way out.
+pub async fn resolve_target_pod(
+    kubeconfig_id: &str,
+    namespace: &str,
+    resource_type: &str,
+    resource_name: &str,
+    pool: &sqlx::sqlite::SqlitePool,
+) -> Result<String, String> {
+    let client = build_client(kubeconfig_id, pool).await?;
+    find_target_pod(client, namespace, resource_type, resource_name).await
+}
+
+/// `resolve_target_pod` with a client the caller built, such as an
+/// untracked one for background work.
+pub async fn find_target_pod(
+    client: Client,
+    namespace: &str,
+    resource_type: &str,
+    resource_name: &str,
+) -> Result<String, String> {
+    let selector = match resource_type {
+        "deployment" => {
+            let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+            let deployment = api
+                .get(resource_name)
+                .await
+                .map_err(|e| format!("Failed to get deployment/{resource_name}: {e}"))?;
+            deployment
+                .spec
+                .map(|spec| label_selector_to_string(&spec.selector))
+                .transpose()?
+                .unwrap_or_default()
         }
+        "statefulset" => {
+            let api: Api<StatefulSet> = Api::namespaced(client.clone(), namespace);
+            let statefulset = api
+                .get(resource_name)
+                .await
+                .map_err(|e| format!("Failed to get statefulset/{resource_name}: {e}"))?;
+            statefulset
+                .spec
+                .map(|spec| label_selector_to_string(&spec.selector))
+                .transpose()?
+                .unwrap_or_default()
+        }
+        "service" => {
+            let api: Api<Service> = Api::namespaced(client.clone(), namespace);
+            let service = api
+                .get(resource_name)
+                .await
+                .map_err(|e| format!("Failed to get service/{resource_name}: {e}"))?;
+            service
+                .spec
+                .and_then(|spec| spec.selector)
+                .map(|labels| {
+                    labels
+                        .iter()
+                        .map(|(k, v)| format!("{k}={v}"))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                })
+                .unwrap_or_default()
+        }
+        "selector" => resource_name.to_string(),
+        other => return Err(format!("Cannot resolve pods for resource type '{other}'")),
+    };
+
+    if selector.is_empty() {
+        return Err(format!(
+            "{resource_type}/{resource_name} has an empty selector; refusing to match every pod"
+        ));
     }
 
-    // ------------------------------------------------------------------
-    // 3. Scan pods for DB credentials via env vars.
-    // ------------------------------------------------------------------
-    let pods_api: Api<Pod> = Api::namespaced(client.clone(), &namespace);
-    let pod_list = pods_api
-        .list(&ListParams::default())
+    let pods: Api<Pod> = Api::namespaced(client, namespace);
+    let list = pods
+        .list(&ListParams::default().labels(&selector))
         .await
-        .map_err(|e| format!("Failed to list pods: {}", e))?;
+        .map_err(|e| format!("Failed to list pods for selector '{selector}': {e}"))?;
 
-    for pod in &pod_list.items {
-        let pod_name = pod.metadata.name.clone().unwrap_or_default();
-        let spec = match &pod.spec {
-            Some(s) => s,
-            None => continue,
-        };
+    list.items
+        .into_iter()
+        .filter(|pod| pod.metadata.deletion_timestamp.is_none())
+        .map(pod_to_model)
+        .find(|pod| pod.ready)
+        .map(|pod| pod.name)
+        .ok_or_else(|| format!("No ready pod found for {resource_type}/{resource_name}"))
+}
 
-        for container in &spec.containers {
-            let env_vars = match &container.env {
-                Some(envs) => envs,
-                None => continue,
-            };
+// ---------------------------------------------------------------------------
+// Events
+// ---------------------------------------------------------------------------
 
-            let mut builder = CredBuilder::default();
+#[derive(Debug, Serialize, Clone)]
+pub struct K8sEvent {
+    /// `Normal` or `Warning`.
+    pub event_type: String,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    pub involved_kind: Option<String>,
+    pub involved_name: Option<String>,
+    pub source: Option<String>,
+    pub count: Option<i32>,
+    pub first_seen: Option<String>,
+    pub last_seen: Option<String>,
+}
 
-            for env_var in env_vars {
-                let key = &env_var.name;
+/// Map a lowercase resource type (`pod`, `statefulset`, ...) to its Kind.
+/// Values that already look like a Kind are passed through.
+fn resource_kind(resource_type: &str) -> String {
+    match resource_type {
+        "pod" | "pods" => "Pod".to_string(),
+        "service" | "services" | "svc" => "Service".to_string(),
+        "deployment" | "deployments" | "deploy" => "Deployment".to_string(),
+        "statefulset" | "statefulsets" | "sts" => "StatefulSet".to_string(),
+        "replicaset" | "replicasets" | "rs" => "ReplicaSet".to_string(),
+        "node" | "nodes" => "Node".to_string(),
+        other => {
+            let mut chars = other.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+    }
+}
 
-                // Direct value
-                if let Some(value) = &env_var.value {
-                    if URL_KEYS.contains(&key.as_str()) {
-                        builder.ingest_url(value);
-                    } else {
-                        builder.ingest(key.as_str(), value);
-                    }
-                    continue;
-                }
+fn event_to_model(event: Event) -> K8sEvent {
+    let last_seen = event
+        .last_timestamp
+        .map(|t| t.0.to_rfc3339())
+        .or_else(|| event.event_time.map(|t| t.0.to_rfc3339()))
+        .or_else(|| event.metadata.creation_timestamp.map(|t| t.0.to_rfc3339()));
+    K8sEvent {
+        event_type: event.type_.unwrap_or_else(|| "Normal".to_string()),
+        reason: event.reason,
+        message: event.message,
+        involved_kind: event.involved_object.kind,
+        involved_name: event.involved_object.name,
+        source: event.source.and_then(|s| s.component),
+        count: event.count,
+        first_seen: event.first_timestamp.map(|t| t.0.to_rfc3339()),
+        last_seen,
+    }
+}
 
-                // Resolve secretKeyRef
-                if let Some(value_from) = &env_var.value_from {
-                    if let Some(secret_key_ref) = &value_from.secret_key_ref {
-                        let secret_name = &secret_key_ref.name;
-                        if secret_name.is_empty() {
-                            continue;
-                        }
-                        let secret_key = &secret_key_ref.key;
-
-                        if let Some(secret_data) = secret_cache.get(secret_name.as_str()) {
-                            if let Some(resolved_value) = secret_data.get(secret_key) {
-                                if URL_KEYS.contains(&key.as_str()) {
-                                    builder.ingest_url(resolved_value);
-                                } else {
-                                    builder.ingest(key.as_str(), resolved_value);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+/// Fetch events in a namespace, newest first, optionally filtered to one
+/// involved object given as `kind/name` (e.g. `pod/api-7d9f`) and to
+/// `Warning` events only.
+pub async fn fetch_events(
+    client: Client,
+    namespace: &str,
+    involved_object: Option<&str>,
+    warnings_only: bool,
+) -> Result<Vec<K8sEvent>, String> {
+    let mut fields: Vec<String> = Vec::new();
+    if let Some(object) = involved_object {
+        let (kind, name) = object
+            .split_once('/')
+            .ok_or_else(|| format!("Invalid involved object '{object}', expected kind/name"))?;
+        fields.push(format!("involvedObject.kind={}", resource_kind(kind)));
+        fields.push(format!("involvedObject.name={name}"));
+    }
+    if warnings_only {
+        fields.push("type=Warning".to_string());
+    }
 
-            if builder.has_any() {
-                results.push(
-                    builder.build(format!("pod/{}/env/{}", pod_name, container.name)),
-                );
-            }
-        }
+    let events: Api<Event> = Api::namespaced(client, namespace);
+    let list = events
+        .list(&ListParams::default().fields(&fields.join(",")))
+        .await
+        .map_err(|e| format!("Failed to list events: {e}"))?;
+
+    let mut result: Vec<K8sEvent> = list.items.into_iter().map(event_to_model).collect();
+    // RFC 3339 timestamps in UTC sort lexicographically.
+    result.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn list_events(
+    kubeconfig_id: String,
+    namespace: String,
+    involved_object: Option<String>,
+    warnings_only: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<K8sEvent>, String> {
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    fetch_events(
+        client,
+        &namespace,
+        involved_object.as_deref(),
+        warnings_only.unwrap_or(false),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selector(value: serde_json::Value) -> LabelSelector {
+        serde_json::from_value(value).unwrap()
     }
 
-    // ------------------------------------------------------------------
-    // 4. Sort by confidence descending.
-    // ------------------------------------------------------------------
-    results.sort_by(|a, b| {
-        b.confidence
-            .partial_cmp(&a.confidence)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    #[test]
+    fn renders_label_selectors() {
+        let rendered = label_selector_to_string(&selector(serde_json::json!({
+            "matchLabels": {"app": "web"},
+            "matchExpressions": [
+                {"key": "tier", "operator": "In", "values": ["a", "b"]},
+                {"key": "track", "operator": "NotIn", "values": ["canary"]},
+                {"key": "managed", "operator": "Exists"},
+                {"key": "legacy", "operator": "DoesNotExist"},
+            ],
+        })));
+        assert_eq!(
+            rendered.as_deref(),
+            Ok("app=web,tier in (a,b),track notin (canary),managed,!legacy")
+        );
+    }
 
-    Ok(results)
+    #[test]
+    fn rejects_unknown_selector_operators() {
+        let err = label_selector_to_string(&selector(serde_json::json!({
+            "matchLabels": {"app": "web"},
+            "matchExpressions": [{"key": "tier", "operator": "Gt", "values": ["1"]}],
+        })))
+        .unwrap_err();
+        assert!(err.contains("'Gt'"), "{err}");
+    }
 }
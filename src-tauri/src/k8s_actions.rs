@@ -0,0 +1,494 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use k8s_openapi::api::apps::v1::{Deployment, StatefulSet};
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{DeleteParams, Patch, PatchParams};
+use kube::{Api, Client};
+use serde::Serialize;
+use sqlx::sqlite::SqlitePool;
+use tauri::State;
+
+use crate::k8s::build_client;
+use crate::AppState;
+
+/// How long a confirmation token stays valid after it was issued.
+const CONFIRMATION_TTL: Duration = Duration::from_secs(60);
+
+/// Actions that must be confirmed with a token from `request_confirmation`:
+/// everything that stops or replaces running pods. That is deleting a pod,
+/// a rollout restart, and scaling a workload below its current replica
+/// count. Scaling up needs no token.
+const DESTRUCTIVE_ACTIONS: &[&str] = &["delete_pod", "rollout_restart", "scale_down"];
+
+// ---------------------------------------------------------------------------
+// Models
+// ---------------------------------------------------------------------------
+
+/// A confirmation token waiting to be consumed by a destructive action.
+pub struct PendingConfirmation {
+    scope: String,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfirmationToken {
+    pub token: String,
+    pub action: String,
+    pub target: String,
+    pub expires_in_secs: u64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: String,
+    pub kubeconfig_id: String,
+    pub kubeconfig_name: Option<String>,
+    pub namespace: String,
+    pub action: String,
+    pub target: String,
+    pub detail: Option<String>,
+    pub outcome: String,
+    pub error_msg: Option<String>,
+    pub created_at: String,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// What a confirmation token is bound to: one action on one object.
+fn confirmation_scope(kubeconfig_id: &str, namespace: &str, action: &str, target: &str) -> String {
+    format!("{kubeconfig_id}/{namespace}/{action}/{target}")
+}
+
+/// Register a token confirming the action `scope` names, valid for
+/// `CONFIRMATION_TTL` from `now`.
+fn issue_confirmation(
+    pending: &mut HashMap<String, PendingConfirmation>,
+    scope: String,
+    now: Instant,
+) -> String {
+    let token = uuid::Uuid::new_v4().to_string();
+    pending.insert(
+        token.clone(),
+        PendingConfirmation {
+            scope,
+            expires_at: now + CONFIRMATION_TTL,
+        },
+    );
+    token
+}
+
+/// Consume a confirmation token. Fails if it is missing, unknown, expired,
+/// or was issued for a different action or target. Expired tokens are
+/// dropped on the way.
+fn consume_confirmation(
+    pending: &mut HashMap<String, PendingConfirmation>,
+    token: Option<&str>,
+    scope: &str,
+    now: Instant,
+) -> Result<(), String> {
+    let token = token.ok_or("This action requires a confirmation token")?;
+
+    pending.retain(|_, p| p.expires_at > now);
+    match pending.remove(token) {
+        Some(p) if p.scope == scope => Ok(()),
+        Some(_) => Err("Confirmation token was issued for a different action".to_string()),
+        None => Err("Confirmation token is invalid or has expired".to_string()),
+    }
+}
+
+/// Audit action for setting `replicas` on a workload running `current`:
+/// `scale_down`, which needs confirmation, below it, otherwise `scale`.
+fn scale_action(replicas: i32, current: i32) -> &'static str {
+    if replicas < current {
+        "scale_down"
+    } else {
+        "scale"
+    }
+}
+
+/// Append an entry to the local audit log. Best-effort: a failing insert
+/// never masks the outcome of the action itself.
+async fn record_audit(
+    pool: &SqlitePool,
+    kubeconfig_id: &str,
+    namespace: &str,
+    action: &str,
+    target: &str,
+    detail: Option<String>,
+    result: &Result<(), String>,
+) {
+    let id = uuid::Uuid::new_v4().to_string();
+    let (outcome, error_msg) = match result {
+        Ok(()) => ("success", None),
+        Err(e) => ("error", Some(e.as_str())),
+    };
+
+    if let Err(e) = sqlx::query(
+        "INSERT INTO audit_log (id, kubeconfig_id, kubeconfig_name, namespace, action, target, detail, outcome, error_msg) \
+         VALUES (?, ?, (SELECT name FROM kubeconfigs WHERE id = ?), ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(kubeconfig_id)
+    .bind(kubeconfig_id)
+    .bind(namespace)
+    .bind(action)
+    .bind(target)
+    .bind(&detail)
+    .bind(outcome)
+    .bind(error_msg)
+    .execute(pool)
+    .await
+    {
+        log::error!("Failed to record audit entry for {action} {target}: {e}");
+    }
+}
+
+/// Check the confirmation for `action` on `target` and use up its token.
+///
+/// Call this right before the API request so a failure building the client
+/// does not cost the user their confirmation. Rejections are written to the
+/// audit log like failed actions.
+async fn confirm_action(
+    state: &AppState,
+    kubeconfig_id: &str,
+    namespace: &str,
+    action: &str,
+    target: &str,
+    detail: Option<String>,
+    token: Option<&str>,
+) -> Result<(), String> {
+    let scope = confirmation_scope(kubeconfig_id, namespace, action, target);
+    let mut pending = state.pending_confirmations.lock().await;
+    let result = consume_confirmation(&mut pending, token, &scope, Instant::now())
+        .map_err(|e| format!("Confirmation rejected: {e}"));
+    drop(pending);
+    if result.is_err() {
+        record_audit(
+            &state.db,
+            kubeconfig_id,
+            namespace,
+            action,
+            target,
+            detail,
+            &result,
+        )
+        .await;
+    }
+    result
+}
+
+/// Current replica count of a Deployment or StatefulSet.
+async fn current_replicas(
+    client: Client,
+    namespace: &str,
+    kind: &str,
+    name: &str,
+) -> Result<i32, String> {
+    let scale = match kind {
+        "deployment" => {
+            Api::<Deployment>::namespaced(client, namespace)
+                .get_scale(name)
+                .await
+        }
+        _ => {
+            Api::<StatefulSet>::namespaced(client, namespace)
+                .get_scale(name)
+                .await
+        }
+    }
+    .map_err(|e| format!("Failed to read the scale of {kind}/{name}: {e}"))?;
+    Ok(scale.spec.and_then(|spec| spec.replicas).unwrap_or(0))
+}
+
+fn validate_workload_kind(kind: &str) -> Result<(), String> {
+    match kind {
+        "deployment" | "statefulset" => Ok(()),
+        other => Err(format!(
+            "Unsupported workload kind '{other}'. Must be deployment or statefulset"
+        )),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Issue a short-lived token confirming one destructive action.
+///
+/// `action` is `delete_pod` (target: `pod/<name>`), or `rollout_restart`
+/// or `scale_down` (target: `deployment/<name>` or `statefulset/<name>`).
+/// Targets are written the same way in the audit log.
+#[tauri::command]
+pub async fn request_confirmation(
+    kubeconfig_id: String,
+    namespace: String,
+    action: String,
+    target: String,
+    state: State<'_, AppState>,
+) -> Result<ConfirmationToken, String> {
+    if !DESTRUCTIVE_ACTIONS.contains(&action.as_str()) {
+        return Err(format!("Action '{action}' does not need confirmation"));
+    }
+
+    let scope = confirmation_scope(&kubeconfig_id, &namespace, &action, &target);
+    let mut pending = state.pending_confirmations.lock().await;
+    let token = issue_confirmation(&mut pending, scope, Instant::now());
+
+    Ok(ConfirmationToken {
+        token,
+        action,
+        target,
+        expires_in_secs: CONFIRMATION_TTL.as_secs(),
+    })
+}
+
+/// Restart every pod of a Deployment or StatefulSet, like
+/// `kubectl rollout restart` (bumps the pod template's `restartedAt`).
+/// Needs a `rollout_restart` confirmation token.
+#[tauri::command]
+pub async fn rollout_restart(
+    kubeconfig_id: String,
+    namespace: String,
+    kind: String,
+    name: String,
+    confirmation_token: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    validate_workload_kind(&kind)?;
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+
+    let target = format!("{kind}/{name}");
+    confirm_action(
+        &state,
+        &kubeconfig_id,
+        &namespace,
+        "rollout_restart",
+        &target,
+        None,
+        confirmation_token.as_deref(),
+    )
+    .await?;
+
+    let result = match kind.as_str() {
+        "deployment" => Api::<Deployment>::namespaced(client, &namespace)
+            .restart(&name)
+            .await
+            .map(|_| ()),
+        _ => Api::<StatefulSet>::namespaced(client, &namespace)
+            .restart(&name)
+            .await
+            .map(|_| ()),
+    }
+    .map_err(|e| format!("Failed to restart {target}: {e}"));
+
+    record_audit(
+        &state.db,
+        &kubeconfig_id,
+        &namespace,
+        "rollout_restart",
+        &target,
+        None,
+        &result,
+    )
+    .await;
+    result
+}
+
+/// Set the replica count of a Deployment or StatefulSet.
+///
+/// Scaling below the current replica count stops pods and needs a
+/// `scale_down` confirmation token.
+#[tauri::command]
+pub async fn scale_workload(
+    kubeconfig_id: String,
+    namespace: String,
+    kind: String,
+    name: String,
+    replicas: i32,
+    confirmation_token: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    validate_workload_kind(&kind)?;
+    if replicas < 0 {
+        return Err("Replica count cannot be negative".to_string());
+    }
+
+    let target = format!("{kind}/{name}");
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    let current = current_replicas(client.clone(), &namespace, &kind, &name).await?;
+    let action = scale_action(replicas, current);
+    if DESTRUCTIVE_ACTIONS.contains(&action) {
+        confirm_action(
+            &state,
+            &kubeconfig_id,
+            &namespace,
+            action,
+            &target,
+            Some(format!("replicas={replicas}")),
+            confirmation_token.as_deref(),
+        )
+        .await?;
+    }
+
+    let patch = Patch::Merge(serde_json::json!({ "spec": { "replicas": replicas } }));
+    let params = PatchParams::default();
+
+    let result = match kind.as_str() {
+        "deployment" => Api::<Deployment>::namespaced(client, &namespace)
+            .patch_scale(&name, &params, &patch)
+            .await
+            .map(|_| ()),
+        _ => Api::<StatefulSet>::namespaced(client, &namespace)
+            .patch_scale(&name, &params, &patch)
+            .await
+            .map(|_| ()),
+    }
+    .map_err(|e| format!("Failed to scale {target}: {e}"));
+
+    record_audit(
+        &state.db,
+        &kubeconfig_id,
+        &namespace,
+        action,
+        &target,
+        Some(format!("replicas={replicas}")),
+        &result,
+    )
+    .await;
+    result
+}
+
+/// Delete a pod. Needs a `delete_pod` confirmation token.
+#[tauri::command]
+pub async fn delete_pod(
+    kubeconfig_id: String,
+    namespace: String,
+    pod: String,
+    confirmation_token: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    let target = format!("pod/{pod}");
+    confirm_action(
+        &state,
+        &kubeconfig_id,
+        &namespace,
+        "delete_pod",
+        &target,
+        None,
+        confirmation_token.as_deref(),
+    )
+    .await?;
+
+    let result = Api::<Pod>::namespaced(client, &namespace)
+        .delete(&pod, &DeleteParams::default())
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("Failed to delete {target}: {e}"));
+
+    record_audit(
+        &state.db,
+        &kubeconfig_id,
+        &namespace,
+        "delete_pod",
+        &target,
+        None,
+        &result,
+    )
+    .await;
+    result
+}
+
+/// Most recent workload actions, newest first.
+#[tauri::command]
+pub async fn list_audit_log(
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<AuditEntry>, String> {
+    let limit = limit.unwrap_or(100);
+
+    sqlx::query_as::<_, AuditEntry>(
+        "SELECT id, kubeconfig_id, kubeconfig_name, namespace, action, target, detail, outcome, error_msg, created_at \
+         FROM audit_log ORDER BY created_at DESC LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| format!("Failed to list audit log: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(action: &str, target: &str) -> String {
+        confirmation_scope("kc", "default", action, target)
+    }
+
+    #[test]
+    fn consumes_a_token_once() {
+        let mut pending = HashMap::new();
+        let now = Instant::now();
+        let token = issue_confirmation(&mut pending, scope("delete_pod", "pod/web-0"), now);
+
+        let scope = scope("delete_pod", "pod/web-0");
+        assert_eq!(
+            consume_confirmation(&mut pending, Some(&token), &scope, now),
+            Ok(())
+        );
+        let err = consume_confirmation(&mut pending, Some(&token), &scope, now).unwrap_err();
+        assert!(err.contains("invalid or has expired"), "{err}");
+
+        let err = consume_confirmation(&mut pending, None, &scope, now).unwrap_err();
+        assert!(err.contains("requires a confirmation token"), "{err}");
+    }
+
+    #[test]
+    fn expires_tokens_after_the_ttl() {
+        let mut pending = HashMap::new();
+        let issued = Instant::now();
+        let scope = scope("rollout_restart", "deployment/web");
+        let late = issue_confirmation(&mut pending, scope.clone(), issued);
+        let in_time = issue_confirmation(&mut pending, scope.clone(), issued);
+
+        let just_before = issued + CONFIRMATION_TTL - Duration::from_millis(1);
+        assert!(consume_confirmation(&mut pending, Some(&in_time), &scope, just_before).is_ok());
+
+        let expired = issued + CONFIRMATION_TTL;
+        let err = consume_confirmation(&mut pending, Some(&late), &scope, expired).unwrap_err();
+        assert!(err.contains("expired"), "{err}");
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn scopes_tokens_to_one_action_and_target() {
+        let now = Instant::now();
+        let issued = scope("scale_down", "deployment/web");
+        let others = [
+            confirmation_scope("other-kc", "default", "scale_down", "deployment/web"),
+            confirmation_scope("kc", "prod", "scale_down", "deployment/web"),
+            scope("rollout_restart", "deployment/web"),
+            scope("scale_down", "deployment/api"),
+        ];
+        for other in others {
+            let mut pending = HashMap::new();
+            let token = issue_confirmation(&mut pending, issued.clone(), now);
+            let err = consume_confirmation(&mut pending, Some(&token), &other, now).unwrap_err();
+            assert!(err.contains("different action"), "{other}: {err}");
+            // A mismatched use still spends the token
+            assert!(pending.is_empty());
+        }
+    }
+
+    #[test]
+    fn only_scaling_down_needs_confirmation() {
+        assert_eq!(scale_action(1, 3), "scale_down");
+        assert_eq!(scale_action(3, 3), "scale");
+        assert_eq!(scale_action(5, 3), "scale");
+        assert!(DESTRUCTIVE_ACTIONS.contains(&scale_action(1, 3)));
+        assert!(!DESTRUCTIVE_ACTIONS.contains(&scale_action(5, 3)));
+    }
+}
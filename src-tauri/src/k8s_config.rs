@@ -0,0 +1,368 @@
+use std::collections::BTreeMap;
+
+use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+use k8s_openapi::ByteString;
+use kube::api::ListParams;
+use kube::Api;
+use serde::Serialize;
+use tauri::State;
+
+use crate::auth::VaultState;
+use crate::k8s::build_client;
+use crate::vault::add_vault_secret;
+use crate::AppState;
+
+// ---------------------------------------------------------------------------
+// Models
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+pub struct K8sConfigMap {
+    pub name: String,
+    pub namespace: String,
+    /// `data` and `binaryData` entries, masked.
+    pub entries: Vec<K8sConfigEntry>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct K8sSecretSummary {
+    pub name: String,
+    pub namespace: String,
+    /// Secret type, e.g. `Opaque` or `kubernetes.io/tls`.
+    pub secret_type: String,
+    pub keys: Vec<String>,
+    pub created_at: Option<String>,
+}
+
+/// A masked ConfigMap or Secret entry.
+#[derive(Debug, Serialize)]
+pub struct K8sConfigEntry {
+    pub key: String,
+    /// Decoded size in bytes.
+    pub size: usize,
+    /// True when the value is not valid UTF-8.
+    pub binary: bool,
+    /// Always `None` here; use `reveal_configmap_value` or
+    /// `reveal_secret_value` to read a value.
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct K8sSecret {
+    pub name: String,
+    pub namespace: String,
+    pub secret_type: String,
+    pub entries: Vec<K8sConfigEntry>,
+    pub created_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevealedValue {
+    pub key: String,
+    /// The value as text, or hex-encoded when `binary` is set.
+    pub value: String,
+    pub binary: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+fn masked_entry(key: &str, bytes: &[u8]) -> K8sConfigEntry {
+    K8sConfigEntry {
+        key: key.to_string(),
+        size: bytes.len(),
+        binary: std::str::from_utf8(bytes).is_err(),
+        value: None,
+    }
+}
+
+/// The value of one entry as text, hex-encoded when it is not UTF-8.
+fn revealed_value(key: String, bytes: &[u8]) -> RevealedValue {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => RevealedValue {
+            key,
+            value: text.to_string(),
+            binary: false,
+        },
+        Err(_) => RevealedValue {
+            key,
+            value: hex::encode(bytes),
+            binary: true,
+        },
+    }
+}
+
+fn configmap_entries(cm: &ConfigMap) -> Vec<K8sConfigEntry> {
+    let text = cm
+        .data
+        .iter()
+        .flatten()
+        .map(|(key, value)| masked_entry(key, value.as_bytes()));
+    let binary = cm
+        .binary_data
+        .iter()
+        .flatten()
+        .map(|(key, value)| masked_entry(key, &value.0));
+    text.chain(binary).collect()
+}
+
+fn secret_type(secret: &Secret) -> String {
+    secret.type_.clone().unwrap_or_else(|| "Opaque".to_string())
+}
+
+fn secret_keys(secret: &Secret) -> Vec<String> {
+    secret
+        .data
+        .as_ref()
+        .map(|data| data.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn secret_created_at(secret: &Secret) -> Option<String> {
+    secret
+        .metadata
+        .creation_timestamp
+        .as_ref()
+        .map(|t| t.0.to_rfc3339())
+}
+
+/// Vault category for a copied secret, derived from the Kubernetes secret
+/// type and, for opaque secrets, the key name.
+fn vault_category(secret_type: &str, key: Option<&str>) -> &'static str {
+    match secret_type {
+        "kubernetes.io/tls" => return "certificate",
+        "kubernetes.io/ssh-auth" => return "ssh_key",
+        "kubernetes.io/basic-auth" => return "password",
+        "kubernetes.io/service-account-token" => return "token",
+        _ => {}
+    }
+
+    let key = key.unwrap_or_default().to_lowercase();
+    if key.contains("password") || key.contains("passwd") {
+        "password"
+    } else if key.contains("token") || key.contains("api_key") || key.contains("apikey") {
+        "token"
+    } else if key.ends_with(".crt") || key.ends_with(".pem") {
+        "certificate"
+    } else {
+        "other"
+    }
+}
+
+/// Render a whole secret as `KEY=value` lines. Binary values are skipped.
+fn secret_to_env(data: &BTreeMap<String, ByteString>) -> Vec<u8> {
+    let mut out = String::new();
+    for (key, value) in data {
+        if let Ok(text) = std::str::from_utf8(&value.0) {
+            out.push_str(&format!("{key}={text}\n"));
+        }
+    }
+    out.into_bytes()
+}
+
+async fn fetch_configmap(
+    kubeconfig_id: &str,
+    namespace: &str,
+    name: &str,
+    state: &AppState,
+) -> Result<ConfigMap, String> {
+    let client = build_client(kubeconfig_id, &state.db).await?;
+    let configmaps: Api<ConfigMap> = Api::namespaced(client, namespace);
+    configmaps
+        .get(name)
+        .await
+        .map_err(|e| format!("Failed to get configmap/{name}: {e}"))
+}
+
+async fn fetch_secret(
+    kubeconfig_id: &str,
+    namespace: &str,
+    name: &str,
+    state: &AppState,
+) -> Result<Secret, String> {
+    let client = build_client(kubeconfig_id, &state.db).await?;
+    let secrets: Api<Secret> = Api::namespaced(client, namespace);
+    secrets
+        .get(name)
+        .await
+        .map_err(|e| format!("Failed to get secret/{name}: {e}"))
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// List ConfigMaps in a namespace with their keys. Values stay masked like
+/// secret values; reveal them one at a time with `reveal_configmap_value`.
+#[tauri::command]
+pub async fn list_configmaps(
+    kubeconfig_id: String,
+    namespace: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<K8sConfigMap>, String> {
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    let configmaps: Api<ConfigMap> = Api::namespaced(client, &namespace);
+    let list = configmaps
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| format!("Failed to list configmaps: {e}"))?;
+
+    Ok(list
+        .items
+        .into_iter()
+        .map(|cm| K8sConfigMap {
+            entries: configmap_entries(&cm),
+            created_at: cm
+                .metadata
+                .creation_timestamp
+                .as_ref()
+                .map(|t| t.0.to_rfc3339()),
+            name: cm.metadata.name.unwrap_or_default(),
+            namespace: cm.metadata.namespace.unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// Explicitly reveal the value of one ConfigMap key.
+#[tauri::command]
+pub async fn reveal_configmap_value(
+    kubeconfig_id: String,
+    namespace: String,
+    name: String,
+    key: String,
+    state: State<'_, AppState>,
+) -> Result<RevealedValue, String> {
+    let cm = fetch_configmap(&kubeconfig_id, &namespace, &name, &state).await?;
+    if let Some(value) = cm.data.as_ref().and_then(|data| data.get(&key)) {
+        return Ok(revealed_value(key, value.as_bytes()));
+    }
+    let bytes = cm
+        .binary_data
+        .as_ref()
+        .and_then(|data| data.get(&key))
+        .ok_or_else(|| format!("ConfigMap {name} has no key '{key}'"))?;
+    Ok(revealed_value(key, &bytes.0))
+}
+
+/// List secrets in a namespace by name, type and keys. No values are returned.
+#[tauri::command]
+pub async fn list_secrets(
+    kubeconfig_id: String,
+    namespace: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<K8sSecretSummary>, String> {
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    let secrets: Api<Secret> = Api::namespaced(client, &namespace);
+    let list = secrets
+        .list(&ListParams::default())
+        .await
+        .map_err(|e| format!("Failed to list secrets: {e}"))?;
+
+    Ok(list
+        .items
+        .iter()
+        .map(|secret| K8sSecretSummary {
+            name: secret.metadata.name.clone().unwrap_or_default(),
+            namespace: secret.metadata.namespace.clone().unwrap_or_default(),
+            secret_type: secret_type(secret),
+            keys: secret_keys(secret),
+            created_at: secret_created_at(secret),
+        })
+        .collect())
+}
+
+/// Get one secret with its keys. Values stay masked; reveal them one at a
+/// time with `reveal_secret_value`.
+#[tauri::command]
+pub async fn get_secret(
+    kubeconfig_id: String,
+    namespace: String,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<K8sSecret, String> {
+    let secret = fetch_secret(&kubeconfig_id, &namespace, &name, &state).await?;
+
+    let entries = secret
+        .data
+        .iter()
+        .flatten()
+        .map(|(key, value)| masked_entry(key, &value.0))
+        .collect();
+
+    Ok(K8sSecret {
+        name,
+        namespace,
+        secret_type: secret_type(&secret),
+        entries,
+        created_at: secret_created_at(&secret),
+    })
+}
+
+/// Explicitly reveal the decoded value of one secret key.
+#[tauri::command]
+pub async fn reveal_secret_value(
+    kubeconfig_id: String,
+    namespace: String,
+    name: String,
+    key: String,
+    state: State<'_, AppState>,
+) -> Result<RevealedValue, String> {
+    let secret = fetch_secret(&kubeconfig_id, &namespace, &name, &state).await?;
+    let bytes = secret
+        .data
+        .as_ref()
+        .and_then(|data| data.get(&key))
+        .ok_or_else(|| format!("Secret {name} has no key '{key}'"))?;
+
+    Ok(revealed_value(key, &bytes.0))
+}
+
+/// Copy a Kubernetes secret into the vault. Returns the new vault secret ID.
+///
+/// With `key` only that value is stored, byte for byte; without it the whole
+/// secret is stored as `KEY=value` lines. `vault_name` and `category`
+/// default to `<namespace>/<name>[/<key>]` and a category guessed from the
+/// secret type.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn copy_secret_to_vault(
+    kubeconfig_id: String,
+    namespace: String,
+    name: String,
+    key: Option<String>,
+    vault_name: Option<String>,
+    category: Option<String>,
+    state: State<'_, AppState>,
+    vault_state: State<'_, VaultState>,
+) -> Result<String, String> {
+    let secret = fetch_secret(&kubeconfig_id, &namespace, &name, &state).await?;
+    let data = secret.data.clone().unwrap_or_default();
+
+    let content = match &key {
+        Some(k) => data
+            .get(k)
+            .map(|v| v.0.clone())
+            .ok_or_else(|| format!("Secret {name} has no key '{k}'"))?,
+        None => secret_to_env(&data),
+    };
+
+    let default_name = match &key {
+        Some(k) => format!("{namespace}/{name}/{k}"),
+        None => format!("{namespace}/{name}"),
+    };
+    let vault_name = vault_name.unwrap_or(default_name);
+    let category = category
+        .unwrap_or_else(|| vault_category(&secret_type(&secret), key.as_deref()).to_string());
+    let notes = format!("Copied from secret/{name} in namespace {namespace}");
+
+    add_vault_secret(
+        vault_name,
+        category,
+        content,
+        None,
+        Some(notes),
+        vault_state,
+    )
+}
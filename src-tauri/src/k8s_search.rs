@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::time::Duration;
+
+use futures::future::join_all;
+use k8s_openapi::api::core::v1::{Namespace, Pod, Service};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use k8s_openapi::NamespaceResourceScope;
+use kube::api::ListParams;
+use kube::{Api, Client, Resource};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::sqlite::SqlitePool;
+use tauri::State;
+
+use crate::k8s::build_client_untracked;
+use crate::AppState;
+
+/// Upper bound for searching a single cluster, so one dead API server cannot
+/// hold up the whole search.
+const SEARCH_CLUSTER_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ---------------------------------------------------------------------------
+// Models
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub kubeconfig_id: String,
+    pub kubeconfig_name: String,
+    pub namespace: String,
+    /// `service` or `pod`.
+    pub kind: String,
+    pub name: String,
+    /// `name`, or `label:<key>=<value>` for the label that matched.
+    pub matched_on: String,
+    pub labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchClusterError {
+    pub kubeconfig_id: String,
+    pub kubeconfig_name: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    /// Clusters that failed or timed out; their hits are missing from `hits`.
+    pub errors: Vec<SearchClusterError>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Case-insensitive match of `needle` (already lowercased) against an object's
+/// name, then against each of its labels rendered as `key=value`.
+fn match_meta(meta: &ObjectMeta, needle: &str) -> Option<String> {
+    let name = meta.name.as_deref().unwrap_or_default();
+    if name.to_lowercase().contains(needle) {
+        return Some("name".to_string());
+    }
+
+    meta.labels.as_ref()?.iter().find_map(|(k, v)| {
+        let pair = format!("{k}={v}");
+        pair.to_lowercase()
+            .contains(needle)
+            .then(|| format!("label:{pair}"))
+    })
+}
+
+/// List a namespaced resource across the whole cluster.
+///
+/// Falls back to listing namespace by namespace when the identity may not
+/// list cluster-wide, and to the kubeconfig's default namespace when it may
+/// not even list namespaces.
+async fn list_everywhere<K>(client: &Client) -> Result<Vec<K>, String>
+where
+    K: Resource<Scope = NamespaceResourceScope> + Clone + DeserializeOwned + Debug,
+    K::DynamicType: Default,
+{
+    let cluster_wide: Api<K> = Api::all(client.clone());
+    let cluster_err = match cluster_wide.list(&ListParams::default()).await {
+        Ok(list) => return Ok(list.items),
+        Err(e) => e,
+    };
+
+    let namespace_api: Api<Namespace> = Api::all(client.clone());
+    let namespaces: Vec<String> = match namespace_api.list(&ListParams::default()).await {
+        Ok(list) => list
+            .items
+            .into_iter()
+            .filter_map(|ns| ns.metadata.name)
+            .collect(),
+        Err(_) => vec![client.default_namespace().to_string()],
+    };
+
+    let lists = join_all(namespaces.iter().map(|ns| {
+        let api: Api<K> = Api::namespaced(client.clone(), ns);
+        async move { api.list(&ListParams::default()).await }
+    }))
+    .await;
+
+    let mut items = Vec::new();
+    let mut any_ok = false;
+    for list in lists.into_iter().flatten() {
+        any_ok = true;
+        items.extend(list.items);
+    }
+
+    if any_ok {
+        Ok(items)
+    } else {
+        Err(cluster_err.to_string())
+    }
+}
+
+/// Build a hit for every object whose name or labels match.
+fn collect_hits<K: Resource>(
+    items: Vec<K>,
+    kind: &str,
+    kubeconfig_id: &str,
+    kubeconfig_name: &str,
+    needle: &str,
+) -> Vec<SearchHit> {
+    items
+        .iter()
+        .filter_map(|obj| {
+            let meta = obj.meta();
+            let matched_on = match_meta(meta, needle)?;
+            Some(SearchHit {
+                kubeconfig_id: kubeconfig_id.to_string(),
+                kubeconfig_name: kubeconfig_name.to_string(),
+                namespace: meta.namespace.clone().unwrap_or_default(),
+                kind: kind.to_string(),
+                name: meta.name.clone().unwrap_or_default(),
+                matched_on,
+                labels: meta.labels.clone().unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+async fn search_cluster(
+    kubeconfig_id: &str,
+    kubeconfig_name: &str,
+    needle: &str,
+    pool: &SqlitePool,
+) -> Result<Vec<SearchHit>, String> {
+    let client = build_client_untracked(kubeconfig_id, pool).await?;
+
+    let (services, pods) = tokio::join!(
+        list_everywhere::<Service>(&client),
+        list_everywhere::<Pod>(&client),
+    );
+
+    let mut hits = collect_hits(services?, "service", kubeconfig_id, kubeconfig_name, needle);
+    hits.extend(collect_hits(pods?, "pod", kubeconfig_id, kubeconfig_name, needle));
+    Ok(hits)
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Search service and pod names and labels across every stored kubeconfig.
+///
+/// Clusters are searched concurrently, each bounded by
+/// `SEARCH_CLUSTER_TIMEOUT`; failures are reported per cluster in `errors`.
+#[tauri::command]
+pub async fn search_resources(
+    query: String,
+    state: State<'_, AppState>,
+) -> Result<SearchResults, String> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        return Err("Search query cannot be empty".to_string());
+    }
+
+    let kubeconfigs: Vec<(String, String)> =
+        sqlx::query_as("SELECT id, name FROM kubeconfigs ORDER BY name")
+            .fetch_all(&state.db)
+            .await
+            .map_err(|e| format!("Failed to list kubeconfigs: {e}"))?;
+
+    let searches = kubeconfigs.iter().map(|(id, name)| {
+        let needle = needle.as_str();
+        let pool = &state.db;
+        async move {
+            let result =
+                tokio::time::timeout(SEARCH_CLUSTER_TIMEOUT, search_cluster(id, name, needle, pool))
+                    .await
+                    .unwrap_or_else(|_| Err("Cluster did not respond in time".to_string()));
+            (id, name, result)
+        }
+    });
+
+    let mut results = SearchResults {
+        hits: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for (id, name, result) in join_all(searches).await {
+        match result {
+            Ok(hits) => results.hits.extend(hits),
+            Err(error) => results.errors.push(SearchClusterError {
+                kubeconfig_id: id.clone(),
+                kubeconfig_name: name.clone(),
+                error,
+            }),
+        }
+    }
+
+    results.hits.sort_by(|a, b| {
+        (&a.kubeconfig_name, &a.namespace, &a.kind, &a.name)
+            .cmp(&(&b.kubeconfig_name, &b.namespace, &b.kind, &b.name))
+    });
+
+    Ok(results)
+}
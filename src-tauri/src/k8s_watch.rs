@@ -0,0 +1,183 @@
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::future::Future;
+
+use futures::StreamExt;
+use k8s_openapi::api::core::v1::{Pod, Service};
+use kube::runtime::{watcher, WatchStreamExt};
+use kube::{Api, Resource, ResourceExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::k8s::{build_client, pod_to_model, service_to_model};
+use crate::AppState;
+
+/// Name of the Tauri event carrying every watch update.
+pub const WATCH_EVENT: &str = "k8s-watch";
+
+// ---------------------------------------------------------------------------
+// Models
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WatchChange<T> {
+    /// Full list after the initial sync or a re-list. Replaces client state.
+    Synced { items: Vec<T> },
+    Added { item: T },
+    Modified { item: T },
+    Deleted { item: T },
+    /// The watch hit an error and is backing off before retrying.
+    Error { message: String },
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WatchEvent<T> {
+    pub watch_id: String,
+    pub resource: &'static str,
+    #[serde(flatten)]
+    pub change: WatchChange<T>,
+}
+
+// ---------------------------------------------------------------------------
+// Watch loop
+// ---------------------------------------------------------------------------
+
+/// Drive a `kube::runtime::watcher` stream and forward it as Tauri events.
+///
+/// The watcher only reports "applied" objects, so names seen since the last
+/// sync are tracked to tell additions from modifications.
+async fn run_watch<K, T>(
+    app: AppHandle,
+    watch_id: String,
+    resource: &'static str,
+    api: Api<K>,
+    convert: fn(K) -> T,
+) where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+    K::DynamicType: Default,
+    T: Serialize + Clone,
+{
+    let emit = |change: WatchChange<T>| {
+        let event = WatchEvent {
+            watch_id: watch_id.clone(),
+            resource,
+            change,
+        };
+        if let Err(e) = app.emit(WATCH_EVENT, event) {
+            log::warn!("Failed to emit {resource} watch event: {e}");
+        }
+    };
+
+    let mut known: HashSet<String> = HashSet::new();
+    let mut pending: Vec<K> = Vec::new();
+    let mut stream = watcher(api, watcher::Config::default())
+        .default_backoff()
+        .boxed();
+
+    while let Some(event) = stream.next().await {
+        match event {
+            Ok(watcher::Event::Init) => pending.clear(),
+            Ok(watcher::Event::InitApply(obj)) => pending.push(obj),
+            Ok(watcher::Event::InitDone) => {
+                known = pending.iter().map(|obj| obj.name_any()).collect();
+                let items = pending.drain(..).map(convert).collect();
+                emit(WatchChange::Synced { items });
+            }
+            Ok(watcher::Event::Apply(obj)) => {
+                let item_is_new = known.insert(obj.name_any());
+                let item = convert(obj);
+                if item_is_new {
+                    emit(WatchChange::Added { item });
+                } else {
+                    emit(WatchChange::Modified { item });
+                }
+            }
+            Ok(watcher::Event::Delete(obj)) => {
+                known.remove(&obj.name_any());
+                emit(WatchChange::Deleted { item: convert(obj) });
+            }
+            Err(e) => emit(WatchChange::Error {
+                message: e.to_string(),
+            }),
+        }
+    }
+}
+
+/// Spawn a watch task and register it under a new ID, which is returned.
+///
+/// The entry is removed when the stream ends. The map stays locked until
+/// the entry is inserted, so a task that ends at once cannot run its
+/// removal first and leave a stale handle behind.
+async fn spawn_watch<F>(state: &AppState, watch: impl FnOnce(String) -> F) -> String
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let watch_id = uuid::Uuid::new_v4().to_string();
+    let mut watches = state.k8s_watches.lock().await;
+
+    let registry = state.k8s_watches.clone();
+    let task = watch(watch_id.clone());
+    let id = watch_id.clone();
+    let handle = tokio::spawn(async move {
+        task.await;
+        registry.lock().await.remove(&id);
+    });
+
+    watches.insert(watch_id.clone(), handle);
+    watch_id
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Start streaming pod changes in `namespace` as `k8s-watch` events.
+///
+/// Returns the watch ID to pass to `stop_watch`.
+#[tauri::command]
+pub async fn watch_pods(
+    kubeconfig_id: String,
+    namespace: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    let api: Api<Pod> = Api::namespaced(client, &namespace);
+
+    Ok(spawn_watch(&state, |watch_id| {
+        run_watch(app, watch_id, "pod", api, pod_to_model)
+    })
+    .await)
+}
+
+/// Start streaming service changes in `namespace` as `k8s-watch` events.
+///
+/// Returns the watch ID to pass to `stop_watch`.
+#[tauri::command]
+pub async fn watch_services(
+    kubeconfig_id: String,
+    namespace: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    let api: Api<Service> = Api::namespaced(client, &namespace);
+
+    Ok(spawn_watch(&state, |watch_id| {
+        run_watch(app, watch_id, "service", api, service_to_model)
+    })
+    .await)
+}
+
+/// Stop a watch started by `watch_pods` or `watch_services`. Stopping a
+/// watch that already ended is not an error.
+#[tauri::command]
+pub async fn stop_watch(watch_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let mut watches = state.k8s_watches.lock().await;
+    if let Some(handle) = watches.remove(&watch_id) {
+        handle.abort();
+    }
+    Ok(())
+}
@@ -3,6 +3,7 @@ use sqlx::sqlite::SqlitePool;
 use tauri::State;
 
 use crate::crypto::{get_or_create_encryption_key, get_encryption_key, secure_encrypt, secure_decrypt};
+use crate::file_activator;
 use crate::AppState;
 
 #[derive(Debug, Serialize, sqlx::FromRow)]
@@ -87,6 +88,83 @@ pub async fn delete_kubeconfig(
     Ok(())
 }
 
+/// Rename a kubeconfig entry. Favorites and forwards keep pointing at the same ID.
+#[tauri::command]
+pub async fn rename_kubeconfig(
+    id: String,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Kubeconfig name cannot be empty".to_string());
+    }
+
+    let result = sqlx::query("UPDATE kubeconfigs SET name = ? WHERE id = ?")
+        .bind(name)
+        .bind(&id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| format!("Failed to rename kubeconfig: {e}"))?;
+
+    if result.rows_affected() == 0 {
+        return Err(format!("Kubeconfig {id} not found"));
+    }
+
+    Ok(())
+}
+
+/// Replace the content of an existing kubeconfig in place.
+///
+/// Goes through the same validation and AES-256-GCM encryption as
+/// `import_kubeconfig`, but keeps the ID so dependent favorites are not
+/// cascaded away (e.g. when rotating a token).
+#[tauri::command]
+pub async fn update_kubeconfig_content(
+    id: String,
+    content: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    validate_kubeconfig_yaml(&content)?;
+
+    let key = get_or_create_encryption_key()?;
+    let encrypted = secure_encrypt(content.as_bytes(), &key)?;
+
+    let result = sqlx::query("UPDATE kubeconfigs SET content = ? WHERE id = ?")
+        .bind(&encrypted)
+        .bind(&id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| format!("Failed to update kubeconfig: {e}"))?;
+
+    if result.rows_affected() == 0 {
+        return Err(format!("Kubeconfig {id} not found"));
+    }
+
+    Ok(())
+}
+
+/// Decrypt a kubeconfig and write it to `path` with permissions `0600`.
+#[tauri::command]
+pub async fn export_kubeconfig(
+    id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let content = get_kubeconfig_content(&id, &state.db).await?;
+    file_activator::activate_file(&path, content.as_bytes())
+}
+
+/// Record that a kubeconfig was just used. Best-effort: failures are ignored.
+pub async fn touch_kubeconfig(id: &str, pool: &SqlitePool) {
+    let now = chrono::Utc::now().to_rfc3339();
+    let _ = sqlx::query("UPDATE kubeconfigs SET last_used = ? WHERE id = ?")
+        .bind(&now)
+        .bind(id)
+        .execute(pool)
+        .await;
+}
+
 /// Retrieve and decrypt the kubeconfig content for a given ID.
 ///
 /// Uses AES-256-GCM decryption with automatic fallback to legacy XOR for
@@ -1,13 +1,25 @@
 mod auth;
 mod crypto;
+mod db_provision;
 mod db;
 mod favorites;
 mod file_activator;
 mod forward;
 mod k8s;
+mod k8s_actions;
+mod k8s_config;
+mod k8s_credentials;
+mod k8s_search;
+mod k8s_watch;
 mod kubeconfig;
 mod ngrok;
+mod pg_decode;
+mod pg_params;
+mod pg_sql;
+mod pg_tls;
 mod pgmanager;
+mod pod_exec;
+mod pod_logs;
 mod ports;
 mod settings;
 mod vault;
@@ -19,7 +31,20 @@ use tauri::Manager;
 
 pub struct AppState {
     pub db: sqlx::sqlite::SqlitePool,
-    pub pg_pools: Arc<tokio::sync::Mutex<HashMap<String, deadpool_postgres::Pool>>>,
+    pub pg_pools: Arc<tokio::sync::Mutex<HashMap<String, pgmanager::PgPool>>>,
+    /// TLS settings each pool in `pg_pools` was created with.
+    pub pg_tls: Arc<tokio::sync::Mutex<HashMap<String, pg_tls::PgTls>>>,
+    /// Queries currently executing, keyed by query ID.
+    pub pg_running_queries: Arc<tokio::sync::Mutex<HashMap<String, pgmanager::RunningQuery>>>,
+    /// Paged results waiting for `pg_fetch_more`, keyed by query ID.
+    pub pg_cursors: Arc<tokio::sync::Mutex<HashMap<String, pgmanager::OpenCursor>>>,
+    /// Open transactions pinned to one client, keyed by connection ID.
+    pub pg_sessions: Arc<tokio::sync::Mutex<HashMap<String, Arc<pgmanager::PgSession>>>>,
+    pub k8s_watches: Arc<tokio::sync::Mutex<HashMap<String, tokio::task::JoinHandle<()>>>>,
+    pub log_streams: Arc<tokio::sync::Mutex<HashMap<String, tokio::task::JoinHandle<()>>>>,
+    pub exec_sessions: Arc<tokio::sync::Mutex<HashMap<String, pod_exec::ExecSession>>>,
+    pub pending_confirmations:
+        Arc<tokio::sync::Mutex<HashMap<String, k8s_actions::PendingConfirmation>>>,
 }
 
 #[tauri::command]
@@ -87,6 +112,14 @@ pub fn run() {
             app.manage(AppState {
                 db: pool,
                 pg_pools: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+                pg_tls: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+                pg_running_queries: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+                pg_cursors: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+                pg_sessions: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+                k8s_watches: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+                log_streams: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+                exec_sessions: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+                pending_confirmations: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
             });
 
             let vault = vault_db::VaultDb::new(vault_app_dir);
@@ -113,15 +146,46 @@ pub fn run() {
             kubeconfig::import_kubeconfig,
             kubeconfig::list_kubeconfigs,
             kubeconfig::delete_kubeconfig,
+            kubeconfig::rename_kubeconfig,
+            kubeconfig::update_kubeconfig_content,
+            kubeconfig::export_kubeconfig,
             favorites::save_favorite,
             favorites::list_favorites,
             favorites::delete_favorite,
             settings::get_setting,
             settings::set_setting,
             k8s::list_namespaces,
+            k8s::can_i,
+            k8s::check_cluster,
+            k8s::list_cluster_statuses,
             k8s::list_services,
             k8s::list_pods,
-            k8s::detect_db_credentials,
+            k8s::list_ingresses,
+            k8s::list_endpoints,
+            k8s::list_events,
+            k8s_actions::request_confirmation,
+            k8s_actions::rollout_restart,
+            k8s_actions::scale_workload,
+            k8s_actions::delete_pod,
+            k8s_actions::list_audit_log,
+            k8s_config::list_configmaps,
+            k8s_config::reveal_configmap_value,
+            k8s_config::list_secrets,
+            k8s_config::get_secret,
+            k8s_config::reveal_secret_value,
+            k8s_config::copy_secret_to_vault,
+            k8s_credentials::detect_db_credentials,
+            db_provision::provision_db_access,
+            k8s_search::search_resources,
+            k8s_watch::watch_pods,
+            k8s_watch::watch_services,
+            k8s_watch::stop_watch,
+            pod_logs::stream_pod_logs,
+            pod_logs::stop_pod_logs,
+            pod_exec::pod_exec_open,
+            pod_exec::pod_exec_write,
+            pod_exec::pod_exec_resize,
+            pod_exec::pod_exec_close,
             forward::create_forward,
             forward::kill_forward,
             forward::restart_forward,
@@ -147,6 +211,17 @@ pub fn run() {
             pgmanager::pg_list_indexes,
             pgmanager::pg_table_row_count,
             pgmanager::pg_execute_query,
+            pgmanager::pg_describe_query,
+            pgmanager::pg_apply_row_changes,
+            pgmanager::pg_cancel_query,
+            pgmanager::pg_fetch_more,
+            pgmanager::pg_close_query,
+            pgmanager::pg_execute_script,
+            pgmanager::pg_begin,
+            pgmanager::pg_commit,
+            pgmanager::pg_rollback,
+            pgmanager::pg_transaction_status,
+            pg_sql::pg_split_sql,
             pgmanager::pg_list_query_history,
             pgmanager::pg_save_query,
             pgmanager::pg_list_saved_queries,
@@ -0,0 +1,907 @@
+use std::error::Error;
+use std::fmt::Write;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use postgres_types::{FromSql, Kind, Type};
+use serde_json::{Map, Value};
+
+// ── Raw values ──────────────────────────────────────────────────────────
+
+/// A column value's binary-format bytes, read without conversion so any
+/// type can be fetched from a row.
+pub struct RawValue<'a>(pub Option<&'a [u8]>);
+
+impl<'a> FromSql<'a> for RawValue<'a> {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Self(Some(raw)))
+    }
+
+    fn from_sql_null(_: &Type) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        Ok(Self(None))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// Cursor over binary-format bytes. Reads past the end return `None`.
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if n > self.buf.len() {
+            return None;
+        }
+        let (head, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Some(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.array::<1>().map(|b| b[0])
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        self.array().map(i16::from_be_bytes)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.array().map(u16::from_be_bytes)
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        self.array().map(i32::from_be_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        self.array().map(i64::from_be_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.array().map(u64::from_be_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.array().map(f32::from_be_bytes)
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        self.array().map(f64::from_be_bytes)
+    }
+
+    /// A length-prefixed value as used in arrays, ranges and composites;
+    /// a length of -1 is `NULL`.
+    fn value(&mut self) -> Option<Option<&'a [u8]>> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Some(None);
+        }
+        self.take(len as usize).map(Some)
+    }
+
+    /// A NUL-terminated string.
+    fn cstring(&mut self) -> Option<&'a str> {
+        let end = self.buf.iter().position(|&b| b == 0)?;
+        let s = std::str::from_utf8(&self.buf[..end]).ok()?;
+        self.buf = &self.buf[end + 1..];
+        Some(s)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.buf)
+    }
+}
+
+// ── Formatting helpers ──────────────────────────────────────────────────
+
+/// Postgres' epoch for dates and timestamps.
+fn pg_epoch() -> chrono::NaiveDateTime {
+    chrono::NaiveDate::from_ymd_opt(2000, 1, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .unwrap_or_default()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(2 + bytes.len() * 2);
+    s.push_str("\\x");
+    for b in bytes {
+        let _ = write!(s, "{b:02x}");
+    }
+    s
+}
+
+fn utf8(bytes: &[u8]) -> Option<String> {
+    std::str::from_utf8(bytes).ok().map(str::to_string)
+}
+
+/// JSON numbers cannot hold NaN or infinities, so those become strings
+/// spelled as Postgres prints them.
+fn float(v: f64) -> Value {
+    if v.is_nan() {
+        Value::String("NaN".to_string())
+    } else if v.is_infinite() {
+        Value::String(if v > 0.0 { "Infinity" } else { "-Infinity" }.to_string())
+    } else {
+        serde_json::json!(v)
+    }
+}
+
+/// `HH:MM:SS` with the fraction trimmed of trailing zeros. Hours may
+/// exceed 24 (for intervals).
+fn clock(micros: i64) -> String {
+    let micros = micros.unsigned_abs();
+    let secs = micros / 1_000_000;
+    let frac = micros % 1_000_000;
+    let mut s = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    if frac > 0 {
+        let digits = format!("{frac:06}");
+        s.push('.');
+        s.push_str(digits.trim_end_matches('0'));
+    }
+    s
+}
+
+fn timestamp(micros: i64) -> Option<chrono::NaiveDateTime> {
+    pg_epoch().checked_add_signed(chrono::Duration::microseconds(micros))
+}
+
+/// Text form of a decoded value for use inside another value's text form,
+/// such as a range bound.
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A value inside a tsvector or tsquery, quoted as Postgres prints it.
+fn ts_quote(lexeme: &str) -> String {
+    format!("'{}'", lexeme.replace('\\', "\\\\").replace('\'', "''"))
+}
+
+// ── Built-in types ──────────────────────────────────────────────────────
+
+fn numeric(r: &mut Reader) -> Option<String> {
+    let ndigits = r.i16()? as i32;
+    let weight = r.i16()? as i32;
+    let sign = r.u16()?;
+    let dscale = r.u16()? as usize;
+    let digits = (0..ndigits).map(|_| r.i16()).collect::<Option<Vec<_>>>()?;
+
+    match sign {
+        0xC000 => return Some("NaN".to_string()),
+        0xD000 => return Some("Infinity".to_string()),
+        0xF000 => return Some("-Infinity".to_string()),
+        _ => {}
+    }
+    let digit = |i: i32| {
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| digits.get(i))
+            .copied()
+            .unwrap_or(0)
+    };
+
+    let mut s = String::new();
+    if sign == 0x4000 {
+        s.push('-');
+    }
+    if weight < 0 {
+        s.push('0');
+    } else {
+        for i in 0..=weight {
+            if i == 0 {
+                let _ = write!(s, "{}", digit(i));
+            } else {
+                let _ = write!(s, "{:04}", digit(i));
+            }
+        }
+    }
+    if dscale > 0 {
+        let mut frac = String::with_capacity(dscale + 4);
+        let mut i = weight + 1;
+        while frac.len() < dscale {
+            let _ = write!(frac, "{:04}", digit(i));
+            i += 1;
+        }
+        frac.truncate(dscale);
+        s.push('.');
+        s.push_str(&frac);
+    }
+    Some(s)
+}
+
+/// `money` is sent as an integer count of the smallest currency unit, and
+/// the binary format does not say how many of those make one unit: that
+/// depends on the server's `lc_monetary`. Two fractional digits are
+/// assumed, as for most locales; under others (such as `ja_JP` with none or
+/// `ar_BH` with three) the decimal point is misplaced.
+fn money(r: &mut Reader) -> Option<String> {
+    let cents = r.i64()?;
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    Some(format!("{sign}{}.{:02}", cents / 100, cents % 100))
+}
+
+fn date(r: &mut Reader) -> Option<String> {
+    let days = r.i32()?;
+    Some(match days {
+        i32::MAX => "infinity".to_string(),
+        i32::MIN => "-infinity".to_string(),
+        _ => {
+            let date = pg_epoch()
+                .date()
+                .checked_add_signed(chrono::Duration::days(days as i64))?;
+            bc_date(date, date.format("%Y-%m-%d").to_string())
+        }
+    })
+}
+
+/// Postgres has no year zero and writes earlier years as `0044-03-15 BC`
+/// where chrono would give `-0043-03-15`.
+fn bc_date(date: impl chrono::Datelike, formatted: String) -> String {
+    let year = date.year();
+    if year > 0 {
+        return formatted;
+    }
+    let rest = formatted.trim_start_matches('-');
+    let rest = rest.split_once('-').map_or(rest, |(_, rest)| rest);
+    format!("{:04}-{rest} BC", 1 - year)
+}
+
+fn timetz(r: &mut Reader) -> Option<String> {
+    let micros = r.i64()?;
+    // Seconds west of UTC, so the sign is flipped for display
+    let offset = -r.i32()?;
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.unsigned_abs();
+    let mut s = format!("{}{sign}{:02}", clock(micros), offset / 3600);
+    if offset % 3600 != 0 {
+        let _ = write!(s, ":{:02}", offset / 60 % 60);
+    }
+    Some(s)
+}
+
+fn timestamp_value(r: &mut Reader, with_zone: bool) -> Option<String> {
+    let micros = r.i64()?;
+    Some(match micros {
+        i64::MAX => "infinity".to_string(),
+        i64::MIN => "-infinity".to_string(),
+        _ if with_zone => timestamp(micros)?.and_utc().to_rfc3339(),
+        _ => {
+            let ts = timestamp(micros)?;
+            bc_date(ts, ts.to_string())
+        }
+    })
+}
+
+/// An interval in Postgres' default output style, such as
+/// `1 year 2 mons 3 days 04:05:06.5`. As there, a field after a negative
+/// one gets an explicit `+`: `-1 days +02:00:00`.
+fn interval(r: &mut Reader) -> Option<String> {
+    let micros = r.i64()?;
+    let days = r.i32()?;
+    let months = r.i32()?;
+
+    let mut parts = Vec::new();
+    let mut after_negative = false;
+    for (n, unit) in [(months / 12, "year"), (months % 12, "mon"), (days, "day")] {
+        if n == 0 {
+            continue;
+        }
+        let sign = if after_negative && n > 0 { "+" } else { "" };
+        let plural = if n == 1 { "" } else { "s" };
+        parts.push(format!("{sign}{n} {unit}{plural}"));
+        after_negative = n < 0;
+    }
+    if micros != 0 || parts.is_empty() {
+        let sign = match micros {
+            m if m < 0 => "-",
+            m if m > 0 && after_negative => "+",
+            _ => "",
+        };
+        parts.push(format!("{sign}{}", clock(micros)));
+    }
+    Some(parts.join(" "))
+}
+
+fn inet(r: &mut Reader, is_cidr_type: bool) -> Option<String> {
+    let family = r.u8()?;
+    let bits = r.u8()?;
+    let _is_cidr = r.u8()?;
+    let len = r.u8()? as usize;
+    let addr = r.take(len)?;
+    let (addr, max_bits) = match family {
+        2 => (
+            Ipv4Addr::from(<[u8; 4]>::try_from(addr).ok()?).to_string(),
+            32,
+        ),
+        3 => (
+            Ipv6Addr::from(<[u8; 16]>::try_from(addr).ok()?).to_string(),
+            128,
+        ),
+        _ => return None,
+    };
+    Some(if is_cidr_type || bits != max_bits {
+        format!("{addr}/{bits}")
+    } else {
+        addr
+    })
+}
+
+fn macaddr(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn bits(r: &mut Reader) -> Option<String> {
+    let len = r.i32()? as usize;
+    let bytes = r.rest();
+    (0..len)
+        .map(|i| {
+            let byte = bytes.get(i / 8)?;
+            Some(if byte & (0x80 >> (i % 8)) != 0 {
+                '1'
+            } else {
+                '0'
+            })
+        })
+        .collect()
+}
+
+fn point(r: &mut Reader) -> Option<String> {
+    Some(format!("({},{})", r.f64()?, r.f64()?))
+}
+
+fn points(r: &mut Reader, n: usize) -> Option<String> {
+    let points = (0..n).map(|_| point(r)).collect::<Option<Vec<_>>>()?;
+    Some(points.join(","))
+}
+
+fn tsvector(r: &mut Reader) -> Option<String> {
+    let count = r.i32()?;
+    let mut lexemes = Vec::new();
+    for _ in 0..count {
+        let mut s = ts_quote(r.cstring()?);
+        let npos = r.u16()?;
+        let positions = (0..npos)
+            .map(|_| {
+                let pos = r.u16()?;
+                let weight = match pos >> 14 {
+                    3 => "A",
+                    2 => "B",
+                    1 => "C",
+                    _ => "",
+                };
+                Some(format!("{}{weight}", pos & 0x3FFF))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if !positions.is_empty() {
+            s.push(':');
+            s.push_str(&positions.join(","));
+        }
+        lexemes.push(s);
+    }
+    Some(lexemes.join(" "))
+}
+
+/// One tsquery node, read in the wire's prefix order (an operator is
+/// followed by its right operand, then its left). Returns the text and the
+/// operator's precedence for parenthesising.
+fn tsquery_node(r: &mut Reader) -> Option<(String, u8)> {
+    match r.u8()? {
+        // Operand
+        1 => {
+            let weight = r.u8()?;
+            let prefix = r.u8()? != 0;
+            let mut s = ts_quote(r.cstring()?);
+            let weights: String = [(8, 'A'), (4, 'B'), (2, 'C'), (1, 'D')]
+                .iter()
+                .filter(|(bit, _)| weight & bit != 0)
+                .map(|&(_, c)| c)
+                .collect();
+            if prefix || !weights.is_empty() {
+                s.push(':');
+                if prefix {
+                    s.push('*');
+                }
+                s.push_str(&weights);
+            }
+            Some((s, u8::MAX))
+        }
+        // Operator
+        2 => {
+            let op = r.u8()?;
+            let distance = if op == 4 { Some(r.i16()?) } else { None };
+            let wrap = |(s, prec): (String, u8), min: u8| {
+                if prec < min {
+                    format!("( {s} )")
+                } else {
+                    s
+                }
+            };
+            match op {
+                1 => {
+                    let operand = tsquery_node(r)?;
+                    Some((format!("!{}", wrap(operand, 4)), 4))
+                }
+                2..=4 => {
+                    let (symbol, prec) = match op {
+                        2 => ("&".to_string(), 2),
+                        3 => ("|".to_string(), 1),
+                        _ => match distance? {
+                            1 => ("<->".to_string(), 3),
+                            d => (format!("<{d}>"), 3),
+                        },
+                    };
+                    let right = tsquery_node(r)?;
+                    let left = tsquery_node(r)?;
+                    let s = format!("{} {symbol} {}", wrap(left, prec), wrap(right, prec + 1));
+                    Some((s, prec))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn tsquery(r: &mut Reader) -> Option<String> {
+    if r.i32()? == 0 {
+        return Some(String::new());
+    }
+    tsquery_node(r).map(|(s, _)| s)
+}
+
+// ── Composite kinds ─────────────────────────────────────────────────────
+
+fn array(r: &mut Reader, element: &Type) -> Option<Value> {
+    let ndim = r.i32()?;
+    let _has_nulls = r.i32()?;
+    let _element_oid = r.u32()?;
+    let dims = (0..ndim)
+        .map(|_| {
+            let len = r.i32()?;
+            let _lower_bound = r.i32()?;
+            usize::try_from(len).ok()
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if dims.is_empty() {
+        return Some(Value::Array(Vec::new()));
+    }
+    array_dim(r, element, &dims)
+}
+
+fn array_dim(r: &mut Reader, element: &Type, dims: &[usize]) -> Option<Value> {
+    let (len, inner) = dims.split_first()?;
+    let items = (0..*len)
+        .map(|_| {
+            if inner.is_empty() {
+                r.value().map(|raw| decode_nullable(element, raw))
+            } else {
+                array_dim(r, element, inner)
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(Value::Array(items))
+}
+
+fn range(r: &mut Reader, subtype: &Type) -> Option<String> {
+    const EMPTY: u8 = 0x01;
+    const LOWER_INCLUSIVE: u8 = 0x02;
+    const UPPER_INCLUSIVE: u8 = 0x04;
+    const LOWER_INFINITE: u8 = 0x08;
+    const UPPER_INFINITE: u8 = 0x10;
+
+    let flags = r.u8()?;
+    if flags & EMPTY != 0 {
+        return Some("empty".to_string());
+    }
+    let mut bound = |infinite: bool| -> Option<String> {
+        if infinite {
+            return Some(String::new());
+        }
+        Some(text_of(&decode_nullable(subtype, r.value()?)))
+    };
+    let lower = bound(flags & LOWER_INFINITE != 0)?;
+    let upper = bound(flags & UPPER_INFINITE != 0)?;
+    Some(format!(
+        "{}{lower},{upper}{}",
+        if flags & LOWER_INCLUSIVE != 0 {
+            '['
+        } else {
+            '('
+        },
+        if flags & UPPER_INCLUSIVE != 0 {
+            ']'
+        } else {
+            ')'
+        },
+    ))
+}
+
+fn multirange(r: &mut Reader, subtype: &Type) -> Option<String> {
+    let count = r.i32()?;
+    let ranges = (0..count)
+        .map(|_| range(&mut Reader::new(r.value()??), subtype))
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("{{{}}}", ranges.join(",")))
+}
+
+fn composite(r: &mut Reader, fields: &[postgres_types::Field]) -> Option<Value> {
+    let count = r.i32()?;
+    let mut object = Map::new();
+    for i in 0..count.max(0) as usize {
+        let _oid = r.u32()?;
+        let raw = r.value()?;
+        let (name, value) = match fields.get(i) {
+            Some(field) => (
+                field.name().to_string(),
+                decode_nullable(field.type_(), raw),
+            ),
+            None => (format!("f{}", i + 1), raw.map_or(Value::Null, fallback)),
+        };
+        object.insert(name, value);
+    }
+    Some(Value::Object(object))
+}
+
+// ── Extension types ─────────────────────────────────────────────────────
+
+fn hstore(r: &mut Reader) -> Option<Value> {
+    let count = r.i32()?;
+    let mut object = Map::new();
+    for _ in 0..count {
+        let key = utf8(r.value()??)?;
+        let value = match r.value()? {
+            Some(raw) => Value::String(utf8(raw)?),
+            None => Value::Null,
+        };
+        object.insert(key, value);
+    }
+    Some(Value::Object(object))
+}
+
+/// pgvector's `vector`: dimension count, an unused word, then `float4`s.
+fn vector(r: &mut Reader) -> Option<Value> {
+    let dim = r.u16()?;
+    let _unused = r.u16()?;
+    let values = (0..dim)
+        .map(|_| r.f32().map(|v| float(v as f64)))
+        .collect::<Option<Vec<_>>>()?;
+    Some(Value::Array(values))
+}
+
+/// Extension types by name. Their binary formats are set by the extension,
+/// so only well-known ones are read; anything else goes to `fallback`.
+fn extension(ty: &Type, r: &mut Reader) -> Option<Value> {
+    let text = |s: String| Some(Value::String(s));
+    match ty.name() {
+        "citext" => text(utf8(r.rest())?),
+        "hstore" => hstore(r),
+        // A format version byte, then the text form
+        "ltree" | "lquery" | "ltxtquery" => {
+            r.u8()?;
+            text(utf8(r.rest())?)
+        }
+        "vector" => vector(r),
+        _ => None,
+    }
+}
+
+/// Values of unknown types: text when the bytes are printable UTF-8,
+/// otherwise hex like `bytea`.
+fn fallback(raw: &[u8]) -> Value {
+    match std::str::from_utf8(raw) {
+        Ok(s) if !s.chars().any(|c| c.is_control() && !c.is_whitespace()) => {
+            Value::String(s.to_string())
+        }
+        _ => Value::String(hex(raw)),
+    }
+}
+
+// ── Decoding ────────────────────────────────────────────────────────────
+
+fn decode_simple(ty: &Type, r: &mut Reader) -> Option<Value> {
+    let string = |s: String| Some(Value::String(s));
+    match *ty {
+        Type::BOOL => Some(Value::Bool(r.u8()? != 0)),
+        Type::INT2 => Some(r.i16()?.into()),
+        Type::INT4 => Some(r.i32()?.into()),
+        Type::INT8 => Some(r.i64()?.into()),
+        Type::OID
+        | Type::XID
+        | Type::CID
+        | Type::REGPROC
+        | Type::REGPROCEDURE
+        | Type::REGOPER
+        | Type::REGOPERATOR
+        | Type::REGCLASS
+        | Type::REGTYPE
+        | Type::REGCONFIG
+        | Type::REGDICTIONARY
+        | Type::REGNAMESPACE
+        | Type::REGROLE
+        | Type::REGCOLLATION => Some(r.u32()?.into()),
+        Type::XID8 => Some(r.u64()?.into()),
+        Type::FLOAT4 => Some(float(r.f32()? as f64)),
+        Type::FLOAT8 => Some(float(r.f64()?)),
+        Type::NUMERIC => string(numeric(r)?),
+        Type::MONEY => string(money(r)?),
+        Type::TEXT
+        | Type::VARCHAR
+        | Type::BPCHAR
+        | Type::NAME
+        | Type::UNKNOWN
+        | Type::XML
+        | Type::REFCURSOR => string(utf8(r.rest())?),
+        Type::CHAR => string((r.u8()? as char).to_string()),
+        Type::JSON => serde_json::from_slice(r.rest()).ok(),
+        Type::JSONB => {
+            // A format version byte, then the text form
+            r.u8()?;
+            serde_json::from_slice(r.rest()).ok()
+        }
+        Type::JSONPATH => {
+            r.u8()?;
+            string(utf8(r.rest())?)
+        }
+        Type::BYTEA => string(hex(r.rest())),
+        Type::UUID => string(uuid::Uuid::from_bytes(r.array()?).to_string()),
+        Type::DATE => string(date(r)?),
+        Type::TIME => string(clock(r.i64()?)),
+        Type::TIMETZ => string(timetz(r)?),
+        Type::TIMESTAMP => string(timestamp_value(r, false)?),
+        Type::TIMESTAMPTZ => string(timestamp_value(r, true)?),
+        Type::INTERVAL => string(interval(r)?),
+        Type::INET => string(inet(r, false)?),
+        Type::CIDR => string(inet(r, true)?),
+        Type::MACADDR => string(macaddr(r.take(6)?)),
+        Type::MACADDR8 => string(macaddr(r.take(8)?)),
+        Type::BIT | Type::VARBIT => string(bits(r)?),
+        Type::POINT => string(point(r)?),
+        Type::LINE => string(format!("{{{},{},{}}}", r.f64()?, r.f64()?, r.f64()?)),
+        Type::LSEG => string(format!("[{}]", points(r, 2)?)),
+        Type::BOX => string(points(r, 2)?),
+        Type::PATH => {
+            let closed = r.u8()? != 0;
+            let n = r.i32()? as usize;
+            let pts = points(r, n)?;
+            string(if closed {
+                format!("({pts})")
+            } else {
+                format!("[{pts}]")
+            })
+        }
+        Type::POLYGON => {
+            let n = r.i32()? as usize;
+            string(format!("({})", points(r, n)?))
+        }
+        Type::CIRCLE => {
+            let center = point(r)?;
+            string(format!("<{center},{}>", r.f64()?))
+        }
+        Type::PG_LSN => {
+            let lsn = r.u64()?;
+            string(format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF))
+        }
+        Type::TID => string(format!("({},{})", r.u32()?, r.u16()?)),
+        Type::TS_VECTOR => string(tsvector(r)?),
+        Type::TSQUERY => string(tsquery(r)?),
+        _ => extension(ty, r),
+    }
+}
+
+fn decode_value(ty: &Type, raw: &[u8]) -> Option<Value> {
+    let mut r = Reader::new(raw);
+    match ty.kind() {
+        Kind::Array(element) => array(&mut r, element),
+        Kind::Domain(base) => decode_value(base, raw),
+        Kind::Enum(_) => utf8(raw).map(Value::String),
+        Kind::Range(subtype) => range(&mut r, subtype).map(Value::String),
+        Kind::Multirange(subtype) => multirange(&mut r, subtype).map(Value::String),
+        Kind::Composite(fields) => composite(&mut r, fields),
+        _ => decode_simple(ty, &mut r),
+    }
+}
+
+fn decode_nullable(ty: &Type, raw: Option<&[u8]>) -> Value {
+    raw.map_or(Value::Null, |raw| decode(ty, raw))
+}
+
+/// Decode a binary-format value of type `ty` to JSON.
+///
+/// Numbers and booleans map to JSON ones, except `numeric` and `money`,
+/// which are exact decimal strings, and non-finite floats, which are
+/// strings. Arrays become (nested) JSON arrays, composites objects keyed by
+/// field name, and `json`/`jsonb` their content. Everything else is a
+/// string in Postgres' own text form (timestamps with a time zone are
+/// given in UTC). Values that cannot be read come back as text or hex.
+///
+/// `money` assumes two fractional digits whatever the server's
+/// `lc_monetary` (see `money`).
+pub fn decode(ty: &Type, raw: &[u8]) -> Value {
+    decode_value(ty, raw).unwrap_or_else(|| fallback(raw))
+}
+
+/// Short name for the kind of a type, reported with result columns.
+pub fn type_kind(ty: &Type) -> &'static str {
+    match ty.kind() {
+        Kind::Simple => "base",
+        Kind::Enum(_) => "enum",
+        Kind::Pseudo => "pseudo",
+        Kind::Array(_) => "array",
+        Kind::Range(_) => "range",
+        Kind::Multirange(_) => "multirange",
+        Kind::Domain(_) => "domain",
+        Kind::Composite(_) => "composite",
+        _ => "base",
+    }
+}
+
+/// SQL spelling of a type: `int4[]` rather than `_int4`, and qualified
+/// with its schema outside `pg_catalog`.
+pub fn type_display_name(ty: &Type) -> String {
+    match ty.kind() {
+        Kind::Array(element) => format!("{}[]", type_display_name(element)),
+        _ if ty.schema() == "pg_catalog" || ty.schema().is_empty() => ty.name().to_string(),
+        _ => format!("{}.{}", ty.schema(), ty.name()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Wire bytes as captured from `COPY ... (FORMAT binary)`.
+    fn wire(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn text(ty: &Type, hex: &str) -> String {
+        match decode(ty, &wire(hex)) {
+            Value::String(s) => s,
+            other => panic!("expected a string, got {other}"),
+        }
+    }
+
+    #[test]
+    fn decodes_numeric() {
+        let cases = [
+            ("0002ffff0000000700010924", "0.0001234"),
+            ("0002000040000002000c1388", "-12.50"),
+            // dscale past the stored digits
+            ("000300010000000304d2162e2328", "12345678.900"),
+            ("00000000c0000000", "NaN"),
+            ("00000000d0000020", "Infinity"),
+            ("00000000f0000020", "-Infinity"),
+        ];
+        for (hex, expected) in cases {
+            assert_eq!(text(&Type::NUMERIC, hex), expected);
+        }
+    }
+
+    #[test]
+    fn decodes_money_with_two_decimals() {
+        assert_eq!(text(&Type::MONEY, "000000000001e240"), "1234.56");
+        assert_eq!(text(&Type::MONEY, "fffffffffffffffb"), "-0.05");
+    }
+
+    #[test]
+    fn decodes_dates() {
+        assert_eq!(text(&Type::DATE, "fff49d7b"), "0044-03-15 BC");
+        assert_eq!(text(&Type::DATE, "7fffffff"), "infinity");
+        assert_eq!(text(&Type::DATE, "80000000"), "-infinity");
+        assert_eq!(
+            text(&Type::TIMESTAMP, "ff1af9e74e1f8800"),
+            "0044-03-15 10:00:00 BC"
+        );
+    }
+
+    #[test]
+    fn decodes_intervals() {
+        let cases = [
+            (
+                "fffffffc936c9e6000000003fffffff2",
+                "-1 years -2 mons +3 days -04:05:06.5",
+            ),
+            (
+                "000000036c9361a0000000030000000e",
+                "1 year 2 mons 3 days 04:05:06.5",
+            ),
+            ("00000001ad274800ffffffff00000000", "-1 days +02:00:00"),
+            ("0000000000000000ffffffff00000018", "2 years -1 days"),
+            ("000000000000000000000000fffffffd", "-3 mons"),
+            ("00000000000000000000000000000000", "00:00:00"),
+        ];
+        for (hex, expected) in cases {
+            assert_eq!(text(&Type::INTERVAL, hex), expected);
+        }
+    }
+
+    #[test]
+    fn decodes_network_addresses() {
+        assert_eq!(text(&Type::INET, "02200004c0a80001"), "192.168.0.1");
+        assert_eq!(text(&Type::INET, "020800040a000000"), "10.0.0.0/8");
+        assert_eq!(
+            text(&Type::INET, "0340001000000000000000000000000000000001"),
+            "::1/64"
+        );
+        assert_eq!(text(&Type::CIDR, "020801040a000000"), "10.0.0.0/8");
+    }
+
+    #[test]
+    fn decodes_arrays_with_nulls() {
+        let raw = wire(
+            "000000020000000100000017000000020000000100000002000000010000000400000001\
+             ffffffff00000004000000030000000400000004",
+        );
+        assert_eq!(decode(&Type::INT4_ARRAY, &raw), json!([[1, null], [3, 4]]));
+    }
+
+    #[test]
+    fn decodes_ranges() {
+        assert_eq!(text(&Type::INT4_RANGE, "01"), "empty");
+        assert_eq!(
+            text(&Type::INT4_RANGE, "0200000004000000010000000400000005"),
+            "[1,5)"
+        );
+        assert_eq!(
+            text(&Type::NUM_RANGE, "0c0000000a00010000000000000003"),
+            "(,3]"
+        );
+    }
+
+    #[test]
+    fn decodes_text_search_types() {
+        let cases = [
+            (
+                "0000000502020203010000630001000062000100006100",
+                "'a' & ( 'b' | 'c' )",
+            ),
+            (
+                "0000000502020100006300020301000062000100006100",
+                "( 'a' | 'b' ) & 'c'",
+            ),
+            (
+                "0000000502020100006300020201000062000100006100",
+                "'a' & 'b' & 'c'",
+            ),
+            (
+                "0000000402040001010801620002010100006100",
+                "!'a' <-> 'b':*A",
+            ),
+        ];
+        for (hex, expected) in cases {
+            assert_eq!(text(&Type::TSQUERY, hex), expected);
+        }
+        assert_eq!(
+            text(&Type::TS_VECTOR, "0000000261000001c00166617400000200028003"),
+            "'a':1A 'fat':2,3B"
+        );
+        assert_eq!(text(&Type::BIT, "00000005b0"), "10110");
+    }
+}
@@ -0,0 +1,226 @@
+use postgres_types::{Kind, ToSql, Type};
+use serde_json::Value;
+
+/// A bound parameter value.
+pub type ParamValue = Box<dyn ToSql + Sync + Send>;
+
+// ── Helpers ─────────────────────────────────────────────────────────────
+
+/// Types bound from JSON values directly. Everything else is sent as text
+/// and cast by the server, so any type with a text form works.
+fn is_native(ty: &Type) -> bool {
+    matches!(
+        *ty,
+        Type::BOOL
+            | Type::INT2
+            | Type::INT4
+            | Type::INT8
+            | Type::FLOAT4
+            | Type::FLOAT8
+            | Type::TEXT
+            | Type::VARCHAR
+            | Type::BPCHAR
+            | Type::NAME
+    )
+}
+
+/// Text form of a value: strings as they are, anything else as JSON.
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A JSON array as a Postgres array literal such as `{1,NULL,"a b"}`.
+/// Elements are quoted unless they are numbers or booleans; objects are
+/// written as JSON text.
+fn array_literal(value: &Value) -> String {
+    match value {
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(array_literal).collect();
+            format!("{{{}}}", items.join(","))
+        }
+        Value::Null => "NULL".to_string(),
+        Value::Number(_) | Value::Bool(_) => value.to_string(),
+        other => {
+            let text = value_text(other);
+            format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+    }
+}
+
+/// SQL `NULL` for a parameter of type `ty`, which must be native or text.
+fn null_value(ty: &Type) -> ParamValue {
+    match *ty {
+        Type::BOOL => Box::new(None::<bool>),
+        Type::INT2 => Box::new(None::<i16>),
+        Type::INT4 => Box::new(None::<i32>),
+        Type::INT8 => Box::new(None::<i64>),
+        Type::FLOAT4 => Box::new(None::<f32>),
+        Type::FLOAT8 => Box::new(None::<f64>),
+        _ => Box::new(None::<String>),
+    }
+}
+
+fn native_value(ty: &Type, value: &Value) -> Result<ParamValue, String> {
+    if value.is_null() {
+        return Ok(null_value(ty));
+    }
+    let text = value_text(value);
+    let text = text.trim();
+    let invalid = |what: &str| format!("expected {what}, got {value}");
+
+    Ok(match *ty {
+        Type::BOOL => match value {
+            Value::Bool(b) => Box::new(*b),
+            _ => match text.to_lowercase().as_str() {
+                "t" | "true" | "y" | "yes" | "on" | "1" => Box::new(true),
+                "f" | "false" | "n" | "no" | "off" | "0" => Box::new(false),
+                _ => return Err(invalid("a boolean")),
+            },
+        },
+        Type::INT2 => Box::new(text.parse::<i16>().map_err(|_| invalid("a smallint"))?),
+        Type::INT4 => Box::new(text.parse::<i32>().map_err(|_| invalid("an integer"))?),
+        Type::INT8 => Box::new(text.parse::<i64>().map_err(|_| invalid("a bigint"))?),
+        Type::FLOAT4 => Box::new(text.parse::<f32>().map_err(|_| invalid("a number"))?),
+        Type::FLOAT8 => Box::new(text.parse::<f64>().map_err(|_| invalid("a number"))?),
+        _ => Box::new(value_text(value)),
+    })
+}
+
+/// Name of `ty` for use in a cast, schema-qualified so extension and user
+/// types resolve whatever the `search_path`.
+fn type_sql_name(ty: &Type) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+    format!("{}.{}", quote(ty.schema()), quote(ty.name()))
+}
+
+// ── Binding ─────────────────────────────────────────────────────────────
+
+/// Convert JSON `values` for parameters of the given inferred `types`,
+/// named `names` for error messages. JSON `null` binds SQL `NULL`, and a
+/// JSON array for an array type is sent as an array literal.
+///
+/// Returns the values and, per parameter, the type to cast to when the
+/// value is sent as text (see `pg_sql::cast_placeholders`).
+pub fn bind_values(
+    names: &[String],
+    types: &[Type],
+    values: &[Value],
+) -> Result<(Vec<ParamValue>, Vec<Option<String>>), String> {
+    if values.len() != types.len() {
+        return Err(format!(
+            "Query has {} parameter(s) but {} value(s) were given",
+            types.len(),
+            values.len()
+        ));
+    }
+
+    let mut bound: Vec<ParamValue> = Vec::with_capacity(values.len());
+    let mut casts = Vec::with_capacity(values.len());
+    for (i, (ty, value)) in types.iter().zip(values).enumerate() {
+        let name = names
+            .get(i)
+            .cloned()
+            .unwrap_or_else(|| format!("${}", i + 1));
+        if is_native(ty) {
+            casts.push(None);
+            let native = native_value(ty, value)
+                .map_err(|e| format!("Parameter {name} ({}): {e}", ty.name()))?;
+            bound.push(native);
+        } else {
+            casts.push(Some(type_sql_name(ty)));
+            let value = match (ty.kind(), value) {
+                (Kind::Array(_), Value::Array(_)) => Value::String(array_literal(value)),
+                _ => value.clone(),
+            };
+            bound.push(native_value(&Type::TEXT, &value)?);
+        }
+    }
+    Ok((bound, casts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn names(n: usize) -> Vec<String> {
+        (1..=n).map(|i| format!("p{i}")).collect()
+    }
+
+    fn debug(values: &[ParamValue]) -> Vec<String> {
+        values.iter().map(|v| format!("{v:?}")).collect()
+    }
+
+    #[test]
+    fn binds_native_types() {
+        let types = [Type::INT4, Type::BOOL, Type::TEXT, Type::INT8];
+        let values = [json!(" 42 "), json!("yes"), json!(7), Value::Null];
+        let (bound, casts) = bind_values(&names(4), &types, &values).unwrap();
+        assert_eq!(debug(&bound), ["42", "true", "\"7\"", "None"]);
+        assert_eq!(casts, [None, None, None, None]);
+
+        let err = bind_values(&["id".to_string()], &[Type::INT4], &[json!("abc")]).unwrap_err();
+        assert_eq!(err, "Parameter id (int4): expected an integer, got \"abc\"");
+    }
+
+    #[test]
+    fn falls_back_to_text_casts() {
+        let types = [Type::UUID, Type::JSONB, Type::NUMERIC];
+        let values = [
+            json!("8a1c7bde-1d2f-4b5a-9c1e-3f0a2b4c6d8e"),
+            json!({"a": 1}),
+            Value::Null,
+        ];
+        let (bound, casts) = bind_values(&names(3), &types, &values).unwrap();
+        assert_eq!(
+            debug(&bound),
+            [
+                "\"8a1c7bde-1d2f-4b5a-9c1e-3f0a2b4c6d8e\"",
+                "\"{\\\"a\\\":1}\"",
+                "None"
+            ]
+        );
+        assert_eq!(
+            casts,
+            [
+                Some("\"pg_catalog\".\"uuid\"".to_string()),
+                Some("\"pg_catalog\".\"jsonb\"".to_string()),
+                Some("\"pg_catalog\".\"numeric\"".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sends_arrays_as_literals() {
+        let types = [Type::INT4_ARRAY, Type::TEXT_ARRAY, Type::INT4_ARRAY];
+        let values = [
+            json!([1, null, 3]),
+            json!(["a b", "q\"", "back\\slash", null]),
+            json!([[1, 2], [3, 4]]),
+        ];
+        let (bound, casts) = bind_values(&names(3), &types, &values).unwrap();
+        let texts: Vec<String> = bound
+            .iter()
+            .map(|v| format!("{v:?}"))
+            .map(|d| serde_json::from_str::<String>(&d).unwrap())
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                "{1,NULL,3}",
+                r#"{"a b","q\"","back\\slash",NULL}"#,
+                "{{1,2},{3,4}}"
+            ]
+        );
+        assert_eq!(casts[0].as_deref(), Some("\"pg_catalog\".\"_int4\""));
+    }
+
+    #[test]
+    fn rejects_wrong_arity() {
+        let err = bind_values(&names(2), &[Type::INT4, Type::TEXT], &[json!(1)]).unwrap_err();
+        assert_eq!(err, "Query has 2 parameter(s) but 1 value(s) were given");
+    }
+}
@@ -0,0 +1,532 @@
+use std::ops::Range;
+
+use serde::Serialize;
+
+// ── Models ──────────────────────────────────────────────────────────────
+
+/// One statement of a SQL script.
+#[derive(Debug, Serialize, Clone)]
+pub struct SqlStatement {
+    /// Statement text without the terminating semicolon or leading comments.
+    pub text: String,
+    /// 1-based line of the script the statement starts on.
+    pub line: usize,
+}
+
+/// A bind parameter in a statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Placeholder {
+    /// `$1`, `$2`, ...
+    Positional(usize),
+    /// `:name`
+    Named(String),
+}
+
+/// A statement with `:name` placeholders rewritten to `$n`.
+#[derive(Debug, Serialize, Clone)]
+pub struct BoundSql {
+    pub text: String,
+    /// Parameter names in `$n` order: the `:name` names, or `$1`, `$2`, ...
+    /// for positional placeholders.
+    pub params: Vec<String>,
+}
+
+// ── Helpers ─────────────────────────────────────────────────────────────
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// The `$tag$` delimiter starting at `start`, if there is one. Tags follow
+/// identifier rules, so positional parameters like `$1` are not delimiters.
+fn dollar_tag(sql: &str, start: usize) -> Option<&str> {
+    let rest = &sql[start + 1..];
+    let end = rest.find('$')?;
+    let tag = &rest[..end];
+    let valid = match tag.chars().next() {
+        None => true,
+        Some(first) => {
+            (first.is_alphabetic() || first == '_')
+                && tag.chars().all(|c| c.is_alphanumeric() || c == '_')
+        }
+    };
+    valid.then(|| &sql[start..start + end + 2])
+}
+
+/// Byte index just past the end of a quoted string starting at `start`.
+/// A doubled quote stands for itself; with `backslash_escapes` (for `E''`
+/// strings) a backslash escapes the next character.
+fn skip_quoted(sql: &str, start: usize, quote: char, backslash_escapes: bool) -> usize {
+    let mut chars = sql[start + 1..].char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if backslash_escapes && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            if chars.peek().map(|&(_, next)| next) == Some(quote) {
+                chars.next();
+            } else {
+                return start + 1 + i + 1;
+            }
+        }
+    }
+    sql.len()
+}
+
+/// Byte index just past the end of a (possibly nested) block comment.
+fn skip_block_comment(sql: &str, start: usize) -> usize {
+    let bytes = sql.as_bytes();
+    let mut depth = 0;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    sql.len()
+}
+
+/// Byte index just past the comment starting at `i`, if one does.
+fn skip_comment(sql: &str, i: usize) -> Option<usize> {
+    let rest = &sql[i..];
+    if rest.starts_with("--") {
+        Some(rest.find('\n').map_or(sql.len(), |n| i + n + 1))
+    } else if rest.starts_with("/*") {
+        Some(skip_block_comment(sql, i))
+    } else {
+        None
+    }
+}
+
+/// Byte index just past the token starting at `i`: a whole string literal,
+/// quoted identifier or dollar-quoted body, otherwise the one character.
+fn skip_token(sql: &str, i: usize) -> usize {
+    let c = sql[i..].chars().next().unwrap_or_default();
+    let prev = sql[..i].chars().next_back();
+    match c {
+        '\'' => {
+            // E'...' strings treat backslashes as escapes
+            let escaped = matches!(prev, Some('e' | 'E'))
+                && !sql[..i - 1].chars().next_back().is_some_and(is_ident_char);
+            skip_quoted(sql, i, '\'', escaped)
+        }
+        '"' => skip_quoted(sql, i, '"', false),
+        '$' if !prev.is_some_and(is_ident_char) => match dollar_tag(sql, i) {
+            Some(tag) => {
+                let body = i + tag.len();
+                sql[body..]
+                    .find(tag)
+                    .map_or(sql.len(), |end| body + end + tag.len())
+            }
+            None => i + 1,
+        },
+        _ => i + c.len_utf8(),
+    }
+}
+
+// ── Splitting ───────────────────────────────────────────────────────────
+
+/// Whether a statement's leading keywords are `CREATE [OR REPLACE]
+/// FUNCTION` or `PROCEDURE`.
+fn is_routine_definition(keywords: &[String]) -> bool {
+    let routine = |k: Option<&String>| k.is_some_and(|k| k == "FUNCTION" || k == "PROCEDURE");
+    match keywords {
+        [create, or, replace, rest @ ..]
+            if create == "CREATE" && or == "OR" && replace == "REPLACE" =>
+        {
+            routine(rest.first())
+        }
+        [create, rest @ ..] if create == "CREATE" => routine(rest.first()),
+        _ => false,
+    }
+}
+
+/// Split a script into statements on top-level semicolons.
+///
+/// Semicolons inside string literals (including `E''` strings), quoted
+/// identifiers, dollar-quoted bodies and line or nested block comments do
+/// not end a statement. Statements made only of comments are dropped.
+///
+/// SQL-standard routine bodies (`CREATE FUNCTION ... BEGIN ATOMIC ... END`)
+/// are kept whole the way psql does it: inside `CREATE [OR REPLACE]
+/// FUNCTION|PROCEDURE`, `BEGIN` and (within a body) `CASE` open a block
+/// that `END` closes, outside parentheses, and semicolons in an open block
+/// do not end the statement.
+pub fn split_statements(sql: &str) -> Vec<SqlStatement> {
+    let mut statements = Vec::new();
+    // Byte index of the current statement's first non-comment character
+    let mut code_start: Option<usize> = None;
+    // Leading keywords of the current statement, and its block nesting
+    let mut keywords: Vec<String> = Vec::new();
+    let mut paren_depth = 0usize;
+    let mut block_depth = 0usize;
+    let mut i = 0;
+
+    let mut finish = |code_start: &mut Option<usize>, end: usize| {
+        if let Some(start) = code_start.take() {
+            statements.push(SqlStatement {
+                text: sql[start..end].trim_end().to_string(),
+                line: sql[..start].matches('\n').count() + 1,
+            });
+        }
+    };
+
+    while i < sql.len() {
+        if let Some(end) = skip_comment(sql, i) {
+            i = end;
+            continue;
+        }
+        let c = sql[i..].chars().next().unwrap_or_default();
+        if c == ';' && block_depth == 0 {
+            finish(&mut code_start, i);
+            keywords.clear();
+            paren_depth = 0;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += c.len_utf8();
+            continue;
+        }
+
+        code_start.get_or_insert(i);
+        match c {
+            '(' => paren_depth += 1,
+            ')' => paren_depth = paren_depth.saturating_sub(1),
+            _ => {}
+        }
+        let starts_word = (c.is_alphabetic() || c == '_')
+            && !sql[..i].chars().next_back().is_some_and(is_ident_char);
+        if starts_word {
+            let end = sql[i..]
+                .find(|c: char| !is_ident_char(c))
+                .map_or(sql.len(), |n| i + n);
+            let word = sql[i..end].to_uppercase();
+            if is_routine_definition(&keywords) && paren_depth == 0 {
+                match word.as_str() {
+                    "BEGIN" => block_depth += 1,
+                    "CASE" if block_depth > 0 => block_depth += 1,
+                    "END" => block_depth = block_depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            if keywords.len() < 4 {
+                keywords.push(word);
+            }
+            i = end;
+            continue;
+        }
+        i = skip_token(sql, i);
+    }
+    finish(&mut code_start, sql.len());
+
+    statements
+}
+
+/// Quote an identifier for use in generated SQL.
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// First keyword of a statement, uppercased, skipping opening parentheses.
+pub fn first_keyword(statement: &str) -> String {
+    statement
+        .trim_start_matches(|c: char| c == '(' || c.is_whitespace())
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_uppercase()
+}
+
+/// Coarse statement type shown with results: `SELECT` for queries,
+/// `INSERT`/`UPDATE`/`DELETE` for DML, `DDL` for everything else.
+pub fn statement_type(statement: &str) -> &'static str {
+    match first_keyword(statement).as_str() {
+        "SELECT" | "WITH" | "VALUES" | "TABLE" | "EXPLAIN" | "SHOW" => "SELECT",
+        "INSERT" => "INSERT",
+        "UPDATE" => "UPDATE",
+        "DELETE" => "DELETE",
+        _ => "DDL",
+    }
+}
+
+// ── Parameters ──────────────────────────────────────────────────────────
+
+/// Bind parameters outside literals and comments, with their byte ranges.
+///
+/// `:name` must not follow an identifier character or another colon, so
+/// `::type` casts and `arr[lo:hi]` slices are not placeholders.
+pub fn find_placeholders(sql: &str) -> Vec<(Range<usize>, Placeholder)> {
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < sql.len() {
+        if let Some(end) = skip_comment(sql, i) {
+            i = end;
+            continue;
+        }
+
+        let rest = &sql[i..];
+        let prev = sql[..i].chars().next_back();
+        let after_ident = prev.is_some_and(is_ident_char);
+        let word_len = |start: usize, pred: fn(char) -> bool| {
+            rest[start..]
+                .find(|c: char| !pred(c))
+                .unwrap_or(rest.len() - start)
+        };
+
+        if rest.starts_with('$') && !after_ident {
+            let digits = word_len(1, |c| c.is_ascii_digit());
+            if digits > 0 {
+                let end = i + 1 + digits;
+                if let Ok(n) = sql[i + 1..end].parse() {
+                    found.push((i..end, Placeholder::Positional(n)));
+                }
+                i = end;
+                continue;
+            }
+        }
+        if rest.starts_with(':') && !after_ident && prev != Some(':') {
+            let starts_name = rest[1..]
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '_');
+            if starts_name {
+                let len = word_len(1, |c| c.is_alphanumeric() || c == '_');
+                let end = i + 1 + len;
+                found.push((i..end, Placeholder::Named(sql[i + 1..end].to_string())));
+                i = end;
+                continue;
+            }
+        }
+
+        i = skip_token(sql, i);
+    }
+    found
+}
+
+/// Number `:name` placeholders as `$1`, `$2`, ... in order of first use so
+/// the statement can be prepared. A name used twice binds one parameter.
+/// Statements already using `$n` are returned as they are; mixing the two
+/// styles is an error.
+pub fn bind_placeholders(sql: &str) -> Result<BoundSql, String> {
+    let placeholders = find_placeholders(sql);
+    let positional = placeholders
+        .iter()
+        .filter_map(|(_, p)| match p {
+            Placeholder::Positional(n) => Some(*n),
+            Placeholder::Named(_) => None,
+        })
+        .max();
+
+    if let Some(max) = positional {
+        if placeholders
+            .iter()
+            .any(|(_, p)| matches!(p, Placeholder::Named(_)))
+        {
+            return Err("Use either $n or :name parameters, not both".to_string());
+        }
+        return Ok(BoundSql {
+            text: sql.to_string(),
+            params: (1..=max).map(|n| format!("${n}")).collect(),
+        });
+    }
+
+    let mut text = String::with_capacity(sql.len());
+    let mut params: Vec<String> = Vec::new();
+    let mut last = 0;
+    for (range, placeholder) in placeholders {
+        let Placeholder::Named(name) = placeholder else {
+            continue;
+        };
+        let n = match params.iter().position(|p| *p == name) {
+            Some(index) => index + 1,
+            None => {
+                params.push(name);
+                params.len()
+            }
+        };
+        text.push_str(&sql[last..range.start]);
+        text.push_str(&format!("${n}"));
+        last = range.end;
+    }
+    text.push_str(&sql[last..]);
+
+    Ok(BoundSql { text, params })
+}
+
+/// Rewrite `$n` to `CAST($n::text AS type)` where `casts[n - 1]` names a
+/// type, so a value can be sent as text and parsed by the server.
+pub fn cast_placeholders(sql: &str, casts: &[Option<String>]) -> String {
+    let mut text = String::with_capacity(sql.len());
+    let mut last = 0;
+    for (range, placeholder) in find_placeholders(sql) {
+        let Placeholder::Positional(n) = placeholder else {
+            continue;
+        };
+        if let Some(Some(ty)) = n.checked_sub(1).and_then(|i| casts.get(i)) {
+            text.push_str(&sql[last..range.start]);
+            text.push_str(&format!("CAST(${n}::text AS {ty})"));
+            last = range.end;
+        }
+    }
+    text.push_str(&sql[last..]);
+    text
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Split a script into statements, for deciding between
+/// `pg_execute_query` and `pg_execute_script`.
+#[tauri::command]
+pub fn pg_split_sql(sql: String) -> Vec<SqlStatement> {
+    split_statements(&sql)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(sql: &str) -> Vec<String> {
+        split_statements(sql).into_iter().map(|s| s.text).collect()
+    }
+
+    #[test]
+    fn splits_on_top_level_semicolons() {
+        let statements = split_statements("SELECT 1; SELECT 2;\n  SELECT 3");
+        let texts: Vec<_> = statements.iter().map(|s| s.text.as_str()).collect();
+        let lines: Vec<_> = statements.iter().map(|s| s.line).collect();
+        assert_eq!(texts, ["SELECT 1", "SELECT 2", "SELECT 3"]);
+        assert_eq!(lines, [1, 1, 2]);
+        assert!(split_statements(" ;\n; ").is_empty());
+    }
+
+    #[test]
+    fn keeps_semicolons_in_quotes() {
+        assert_eq!(
+            texts(r#"SELECT 'a;b', "c;d"; SELECT 'it''s;'"#),
+            [r#"SELECT 'a;b', "c;d""#, "SELECT 'it''s;'"]
+        );
+        // An unterminated literal runs to the end of the script
+        assert_eq!(texts("SELECT 'a; SELECT 2"), ["SELECT 'a; SELECT 2"]);
+    }
+
+    #[test]
+    fn honours_backslash_escapes_only_in_e_strings() {
+        assert_eq!(
+            texts(r"SELECT E'\';'; SELECT 2"),
+            [r"SELECT E'\';'", "SELECT 2"]
+        );
+        assert_eq!(
+            texts(r"SELECT e'x\''';'; SELECT 2"),
+            [r"SELECT e'x\''';'", "SELECT 2"]
+        );
+        // Plain strings end at the quote after the backslash
+        assert_eq!(
+            texts(r"SELECT 'a\'; SELECT 2"),
+            [r"SELECT 'a\'", "SELECT 2"]
+        );
+        // A trailing `e` of an identifier does not make an E-string
+        assert_eq!(
+            texts(r"SELECT note'\'; SELECT 2"),
+            [r"SELECT note'\'", "SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn keeps_dollar_quoted_bodies_whole() {
+        let create = "CREATE FUNCTION f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql";
+        assert_eq!(texts(&format!("{create}; SELECT 2")), [create, "SELECT 2"]);
+
+        let tagged = "DO $fn$ BEGIN PERFORM $$;$$; END $fn$";
+        assert_eq!(texts(&format!("{tagged}; SELECT 2")), [tagged, "SELECT 2"]);
+
+        // Positional parameters are not dollar quotes
+        assert_eq!(texts("SELECT $1; SELECT $2"), ["SELECT $1", "SELECT $2"]);
+    }
+
+    #[test]
+    fn skips_line_and_nested_block_comments() {
+        let sql = "-- first; still a comment\nSELECT 1; /* a; /* nested; */ still; */ SELECT 2; -- trailing;";
+        let statements = split_statements(sql);
+        let texts: Vec<_> = statements.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, ["SELECT 1", "SELECT 2"]);
+        assert_eq!(statements[0].line, 2);
+        assert!(split_statements("/* only; a comment */ -- and this;").is_empty());
+    }
+
+    #[test]
+    fn keeps_begin_atomic_bodies_whole() {
+        let function = "CREATE OR REPLACE FUNCTION f(x int) RETURNS int LANGUAGE sql\n\
+                        BEGIN ATOMIC\n\
+                        SELECT CASE WHEN x > 0 THEN 1 ELSE 0 END;\n\
+                        SELECT (CASE x WHEN 1 THEN 2 END);\n\
+                        END";
+        assert_eq!(
+            texts(&format!("{function};\nSELECT 1")),
+            [function, "SELECT 1"]
+        );
+
+        let procedure = "create procedure p() begin atomic insert into t values (1); end";
+        assert_eq!(
+            texts(&format!("{procedure}; SELECT 1")),
+            [procedure, "SELECT 1"]
+        );
+
+        // Transaction blocks still split
+        assert_eq!(
+            texts("BEGIN; SELECT CASE WHEN true THEN 1 END; END;"),
+            ["BEGIN", "SELECT CASE WHEN true THEN 1 END", "END"]
+        );
+    }
+
+    #[test]
+    fn numbers_named_placeholders() {
+        let bound =
+            bind_placeholders("SELECT * FROM t WHERE a = :a AND b = :b_2 OR c = :a").unwrap();
+        assert_eq!(
+            bound.text,
+            "SELECT * FROM t WHERE a = $1 AND b = $2 OR c = $1"
+        );
+        assert_eq!(bound.params, ["a", "b_2"]);
+    }
+
+    #[test]
+    fn skips_casts_literals_comments_and_bodies() {
+        let sql = "SELECT :x::int, ':y', \":z\", arr[1:n] -- :c\n/* :d */ $$ :e $$";
+        let bound = bind_placeholders(sql).unwrap();
+        assert_eq!(
+            bound.text,
+            "SELECT $1::int, ':y', \":z\", arr[1:n] -- :c\n/* :d */ $$ :e $$"
+        );
+        assert_eq!(bound.params, ["x"]);
+    }
+
+    #[test]
+    fn keeps_positional_placeholders() {
+        let bound = bind_placeholders("SELECT $2, $1, '$3'").unwrap();
+        assert_eq!(bound.text, "SELECT $2, $1, '$3'");
+        assert_eq!(bound.params, ["$1", "$2"]);
+
+        let err = bind_placeholders("SELECT $1, :a").unwrap_err();
+        assert!(err.contains("not both"), "{err}");
+    }
+
+    #[test]
+    fn casts_text_placeholders() {
+        let casts = [Some("\"pg_catalog\".\"uuid\"".to_string()), None];
+        assert_eq!(
+            cast_placeholders("SELECT $1, $2, $3, '$1' -- $1", &casts),
+            "SELECT CAST($1::text AS \"pg_catalog\".\"uuid\"), $2, $3, '$1' -- $1"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+use deadpool_postgres::{Pool, Runtime, SslMode};
+use native_tls::{Certificate, Identity, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use tokio_postgres::{CancelToken, NoTls};
+
+use crate::auth::VaultState;
+
+// ── Models ──────────────────────────────────────────────────────────────
+
+/// PEM material for a TLS connection, read from vault secrets.
+#[derive(Default)]
+pub struct TlsMaterial {
+    /// One or more CA certificates to trust instead of the system roots.
+    pub ca: Option<Vec<u8>>,
+    /// Client certificate chain, paired with `key`.
+    pub cert: Option<Vec<u8>>,
+    /// PKCS#8 private key for `cert`.
+    pub key: Option<Vec<u8>>,
+}
+
+/// How to negotiate TLS with a server, built from a connection's `ssl_mode`.
+///
+/// Modes follow libpq:
+/// - `disable`: never use TLS.
+/// - `allow` / `prefer`: use TLS when the server offers it, without
+///   verifying the certificate.
+/// - `require`: always use TLS. The certificate is only verified when a CA
+///   is configured, in which case this behaves like `verify-ca`.
+/// - `verify-ca`: always use TLS and verify the chain, not the host name.
+/// - `verify-full`: always use TLS and verify the chain and host name.
+///   Through a port forward the host name is `127.0.0.1`, so the server
+///   certificate must cover it.
+#[derive(Clone)]
+pub struct PgTls {
+    mode: SslMode,
+    connector: Option<MakeTlsConnector>,
+}
+
+// ── Helpers ─────────────────────────────────────────────────────────────
+
+/// Read a vault secret's content. The vault must be unlocked.
+fn read_vault_secret(vault_state: &VaultState, id: &str, what: &str) -> Result<Vec<u8>, String> {
+    vault_state.vault.with_conn(|conn| {
+        conn.query_row(
+            "SELECT content FROM vault_secrets WHERE id = ?1",
+            rusqlite::params![id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to read {what} from vault: {e}"))
+    })
+}
+
+/// Split a PEM bundle into certificates. Input without PEM markers is
+/// treated as a single DER certificate.
+fn parse_certificates(bytes: &[u8]) -> Result<Vec<Certificate>, String> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) if text.contains(BEGIN) => text,
+        _ => {
+            let cert =
+                Certificate::from_der(bytes).map_err(|e| format!("Invalid CA certificate: {e}"))?;
+            return Ok(vec![cert]);
+        }
+    };
+
+    let mut certs = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(BEGIN) {
+        let Some(len) = rest[start..].find(END) else {
+            return Err("Invalid CA certificate: unterminated PEM block".to_string());
+        };
+        let end = start + len + END.len();
+        let cert = Certificate::from_pem(&rest.as_bytes()[start..end])
+            .map_err(|e| format!("Invalid CA certificate: {e}"))?;
+        certs.push(cert);
+        rest = &rest[end..];
+    }
+    Ok(certs)
+}
+
+/// How `ssl_mode` negotiates TLS and whether it verifies the certificate
+/// chain and host name. `require` verifies the chain only when a CA is
+/// configured.
+fn mode_policy(ssl_mode: &str, has_ca: bool) -> Result<(SslMode, bool, bool), String> {
+    match ssl_mode {
+        "disable" => Ok((SslMode::Disable, false, false)),
+        "allow" | "prefer" => Ok((SslMode::Prefer, false, false)),
+        "require" => Ok((SslMode::Require, has_ca, false)),
+        "verify-ca" => Ok((SslMode::Require, true, false)),
+        "verify-full" => Ok((SslMode::Require, true, true)),
+        other => Err(format!("Unknown SSL mode '{other}'")),
+    }
+}
+
+fn build_connector(
+    verify_chain: bool,
+    verify_host: bool,
+    material: &TlsMaterial,
+) -> Result<MakeTlsConnector, String> {
+    let mut builder = TlsConnector::builder();
+    builder.danger_accept_invalid_certs(!verify_chain);
+    builder.danger_accept_invalid_hostnames(!verify_host);
+
+    if let Some(ref ca) = material.ca {
+        for cert in parse_certificates(ca)? {
+            builder.add_root_certificate(cert);
+        }
+    }
+
+    match (&material.cert, &material.key) {
+        (Some(cert), Some(key)) => {
+            let identity = Identity::from_pkcs8(cert, key).map_err(|e| {
+                format!("Invalid client certificate or key (the key must be PKCS#8 PEM): {e}")
+            })?;
+            builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => return Err("A client certificate and key must be configured together".to_string()),
+    }
+
+    let connector = builder
+        .build()
+        .map_err(|e| format!("Failed to set up TLS: {e}"))?;
+    Ok(MakeTlsConnector::new(connector))
+}
+
+// ── PgTls ───────────────────────────────────────────────────────────────
+
+impl PgTls {
+    pub fn new(ssl_mode: &str, material: &TlsMaterial) -> Result<Self, String> {
+        let (mode, verify_chain, verify_host) = mode_policy(ssl_mode, material.ca.is_some())?;
+        if mode == SslMode::Disable {
+            return Ok(Self {
+                mode,
+                connector: None,
+            });
+        }
+
+        Ok(Self {
+            mode,
+            connector: Some(build_connector(verify_chain, verify_host, material)?),
+        })
+    }
+
+    /// Load the CA, client certificate and key from the vault and build the
+    /// TLS settings. Nothing is read from the vault when TLS is disabled.
+    pub fn from_vault(
+        ssl_mode: &str,
+        ca_secret_id: Option<&str>,
+        cert_secret_id: Option<&str>,
+        key_secret_id: Option<&str>,
+        vault_state: &VaultState,
+    ) -> Result<Self, String> {
+        if ssl_mode == "disable" {
+            return Self::new(ssl_mode, &TlsMaterial::default());
+        }
+
+        let read = |id: Option<&str>, what| {
+            id.map(|id| read_vault_secret(vault_state, id, what))
+                .transpose()
+        };
+        let material = TlsMaterial {
+            ca: read(ca_secret_id, "CA certificate")?,
+            cert: read(cert_secret_id, "client certificate")?,
+            key: read(key_secret_id, "client key")?,
+        };
+        Self::new(ssl_mode, &material)
+    }
+
+    /// Create a pool from `cfg` using these TLS settings. `cfg.ssl_mode` is
+    /// overwritten.
+    pub fn create_pool(&self, cfg: &mut deadpool_postgres::Config) -> Result<Pool, String> {
+        cfg.ssl_mode = Some(self.mode);
+        let pool = match self.connector {
+            Some(ref connector) => cfg.create_pool(Some(Runtime::Tokio1), connector.clone()),
+            None => cfg.create_pool(Some(Runtime::Tokio1), NoTls),
+        };
+        pool.map_err(|e| format!("Failed to create pool: {e}"))
+    }
+
+    /// Ask the server to cancel the query running on `token`'s connection.
+    pub async fn cancel(&self, token: &CancelToken) -> Result<(), String> {
+        let result = match self.connector {
+            Some(ref connector) => token.cancel_query(connector.clone()).await,
+            None => token.cancel_query(NoTls).await,
+        };
+        result.map_err(|e| format!("Failed to cancel query: {e}"))
+    }
+
+    /// Open a single connection, driving it on a background task.
+    pub async fn connect(
+        &self,
+        config: &mut tokio_postgres::Config,
+    ) -> Result<tokio_postgres::Client, String> {
+        config.ssl_mode(self.mode.into());
+        let client = match self.connector {
+            Some(ref connector) => {
+                let (client, connection) = config
+                    .connect(connector.clone())
+                    .await
+                    .map_err(|e| format!("Connection failed: {e}"))?;
+                tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        log::error!("pg connection error: {e}");
+                    }
+                });
+                client
+            }
+            None => {
+                let (client, connection) = config
+                    .connect(NoTls)
+                    .await
+                    .map_err(|e| format!("Connection failed: {e}"))?;
+                tokio::spawn(async move {
+                    if let Err(e) = connection.await {
+                        log::error!("pg connection error: {e}");
+                    }
+                });
+                client
+            }
+        };
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Self-signed P-256 certificate for CN=localhost and 127.0.0.1, with its
+    // PKCS#8 key and the same certificate in DER form
+    const CERT_PEM: &str = include_str!("../testdata/self_signed.crt");
+    const KEY_PEM: &str = include_str!("../testdata/self_signed.key");
+    const CERT_DER: &[u8] = include_bytes!("../testdata/self_signed.der");
+
+    #[test]
+    fn maps_ssl_modes() {
+        let cases = [
+            ("disable", false, (SslMode::Disable, false, false)),
+            ("allow", false, (SslMode::Prefer, false, false)),
+            ("prefer", true, (SslMode::Prefer, false, false)),
+            ("require", false, (SslMode::Require, false, false)),
+            ("require", true, (SslMode::Require, true, false)),
+            ("verify-ca", false, (SslMode::Require, true, false)),
+            ("verify-full", true, (SslMode::Require, true, true)),
+        ];
+        for (ssl_mode, has_ca, expected) in cases {
+            assert_eq!(mode_policy(ssl_mode, has_ca), Ok(expected), "{ssl_mode}");
+        }
+        assert!(mode_policy("verify_full", false).is_err());
+    }
+
+    #[test]
+    fn builds_connectors_per_mode() {
+        let none = TlsMaterial::default();
+        let tls = PgTls::new("disable", &none).unwrap();
+        assert!(tls.connector.is_none());
+
+        for ssl_mode in ["allow", "prefer", "require", "verify-ca", "verify-full"] {
+            let tls = PgTls::new(ssl_mode, &none).unwrap();
+            assert!(tls.connector.is_some(), "{ssl_mode}");
+        }
+        assert!(PgTls::new("sometimes", &none).is_err());
+
+        let with_ca = TlsMaterial {
+            ca: Some(CERT_PEM.as_bytes().to_vec()),
+            ..TlsMaterial::default()
+        };
+        assert!(PgTls::new("verify-full", &with_ca).is_ok());
+    }
+
+    #[test]
+    fn requires_client_cert_and_key_together() {
+        let identity = TlsMaterial {
+            cert: Some(CERT_PEM.as_bytes().to_vec()),
+            key: Some(KEY_PEM.as_bytes().to_vec()),
+            ..TlsMaterial::default()
+        };
+        assert!(PgTls::new("require", &identity).is_ok());
+
+        let cert_only = TlsMaterial {
+            cert: Some(CERT_PEM.as_bytes().to_vec()),
+            ..TlsMaterial::default()
+        };
+        let err = PgTls::new("require", &cert_only).err().unwrap();
+        assert!(err.contains("together"), "{err}");
+
+        let bad_key = TlsMaterial {
+            cert: Some(CERT_PEM.as_bytes().to_vec()),
+            key: Some(b"not a key".to_vec()),
+            ..TlsMaterial::default()
+        };
+        assert!(PgTls::new("require", &bad_key).is_err());
+    }
+
+    #[test]
+    fn parses_certificates() {
+        assert_eq!(parse_certificates(CERT_PEM.as_bytes()).unwrap().len(), 1);
+        assert_eq!(parse_certificates(CERT_DER).unwrap().len(), 1);
+
+        // Bundles may have text between the blocks
+        let bundle = format!("# first\n{CERT_PEM}\n# second\n{CERT_PEM}");
+        assert_eq!(parse_certificates(bundle.as_bytes()).unwrap().len(), 2);
+
+        let unterminated = CERT_PEM.replace("-----END CERTIFICATE-----", "");
+        let err = parse_certificates(unterminated.as_bytes()).err().unwrap();
+        assert!(err.contains("unterminated"), "{err}");
+
+        assert!(parse_certificates(b"not a certificate").is_err());
+    }
+}
@@ -1,9 +1,28 @@
-use postgres_types::Type;
+use deadpool_postgres::{ObjectId, PoolConfig, PoolError, Timeouts};
+use futures::TryStreamExt;
+use postgres_types::ToSql;
 use serde::{Deserialize, Serialize};
-use std::time::Instant;
+use sqlx::sqlite::SqlitePool;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tauri::State;
-use tokio_postgres::Row;
-
+use tokio_postgres::error::SqlState;
+use tokio_postgres::{CancelToken, Row};
+
+use crate::auth::VaultState;
+use crate::favorites::Favorite;
+use crate::forward::{
+    create_forward, find_running_forward, restart_forward, wait_for_local_port, ActiveForward,
+};
+use crate::pg_decode::{self, RawValue};
+use crate::pg_params::{bind_values, ParamValue};
+use crate::pg_sql::{
+    bind_placeholders, cast_placeholders, first_keyword, quote_ident, split_statements,
+    statement_type,
+};
+use crate::pg_tls::PgTls;
 use crate::AppState;
 
 // ── Models ──────────────────────────────────────────────────────────────
@@ -21,6 +40,14 @@ pub struct PgConnection {
     #[serde(skip_serializing)]
     pub password: Option<Vec<u8>>,
     pub ssl_mode: String,
+    /// Vault secret holding the CA certificate(s) to verify the server with.
+    pub ssl_ca_secret_id: Option<String>,
+    /// Vault secrets holding the client certificate and its PKCS#8 key.
+    pub ssl_cert_secret_id: Option<String>,
+    pub ssl_key_secret_id: Option<String>,
+    /// Default `statement_timeout` applied to each checked-out client.
+    /// `None` keeps the server's default.
+    pub statement_timeout_ms: Option<i64>,
     pub color: Option<String>,
     pub created_at: String,
     pub last_used: Option<String>,
@@ -31,19 +58,48 @@ pub struct PgConnection {
 #[derive(Debug, Serialize, Clone)]
 pub struct PgColumnMeta {
     pub name: String,
+    /// Column type as written in SQL, e.g. `numeric`, `int4[]` or
+    /// `public.mood`.
     pub data_type: String,
+    pub type_oid: u32,
+    /// `base`, `array`, `enum`, `range`, `multirange`, `domain`,
+    /// `composite` or `pseudo`.
+    pub type_kind: String,
 }
 
 #[derive(Debug, Serialize)]
 pub struct PgQueryResult {
+    /// ID the query ran under, usable with `pg_cancel_query` and, while
+    /// `has_more` is set, `pg_fetch_more` and `pg_close_query`.
+    pub query_id: String,
     pub columns: Vec<PgColumnMeta>,
     pub rows: Vec<Vec<serde_json::Value>>,
+    /// Exact row count, known once every row has been fetched.
     pub total_rows: Option<i64>,
+    /// Planner estimate of the row count, when requested.
+    pub estimated_rows: Option<i64>,
+    /// More rows are waiting in a server-side cursor.
+    pub has_more: bool,
     pub affected_rows: Option<u64>,
     pub duration_ms: u64,
     pub query_type: String,
 }
 
+/// A bind parameter of a statement, as described by `pg_describe_query`.
+#[derive(Debug, Serialize)]
+pub struct PgParamMeta {
+    /// `:name` without the colon, or `$n` for positional parameters.
+    pub name: String,
+    /// Type inferred by the server.
+    pub data_type: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PgQueryDescription {
+    pub params: Vec<PgParamMeta>,
+    pub columns: Vec<PgColumnMeta>,
+}
+
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct PgQueryHistoryEntry {
     pub id: String,
@@ -62,74 +118,47 @@ pub struct PgSavedQuery {
     pub label: String,
     pub sql_text: String,
     pub created_at: String,
+    #[serde(skip)]
+    pub params_json: Option<String>,
+    /// Parameter definitions, decoded from `params_json`.
+    #[sqlx(skip)]
+    pub params: Vec<PgSavedQueryParam>,
+}
+
+/// A parameter stored with a saved query.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PgSavedQueryParam {
+    pub name: String,
+    /// Type to show in the editor; the server's inferred type is used when
+    /// the query runs.
+    pub data_type: Option<String>,
+    pub default_value: Option<serde_json::Value>,
+}
+
+impl PgSavedQuery {
+    fn decode_params(mut self) -> Self {
+        self.params = self
+            .params_json
+            .as_deref()
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default();
+        self
+    }
 }
 
 // ── Row → JSON Helper ─────────────────────────────────────────────────
 
+/// Decode every column of a row; see `pg_decode::decode` for how each type
+/// is rendered.
 fn row_to_json(row: &Row) -> Vec<serde_json::Value> {
-    let mut values = Vec::new();
-    for i in 0..row.len() {
-        let col_type = row.columns()[i].type_();
-        let value = match *col_type {
-            Type::BOOL => row
-                .get::<_, Option<bool>>(i)
-                .map(serde_json::Value::Bool)
-                .unwrap_or(serde_json::Value::Null),
-            Type::INT2 => row
-                .get::<_, Option<i16>>(i)
-                .map(|v| serde_json::Value::Number(v.into()))
-                .unwrap_or(serde_json::Value::Null),
-            Type::INT4 => row
-                .get::<_, Option<i32>>(i)
-                .map(|v| serde_json::Value::Number(v.into()))
-                .unwrap_or(serde_json::Value::Null),
-            Type::INT8 => row
-                .get::<_, Option<i64>>(i)
-                .map(|v| serde_json::Value::Number(v.into()))
-                .unwrap_or(serde_json::Value::Null),
-            Type::FLOAT4 => row
-                .get::<_, Option<f32>>(i)
-                .map(|v| serde_json::json!(v))
-                .unwrap_or(serde_json::Value::Null),
-            Type::FLOAT8 => row
-                .get::<_, Option<f64>>(i)
-                .map(|v| serde_json::json!(v))
-                .unwrap_or(serde_json::Value::Null),
-            Type::TEXT | Type::VARCHAR | Type::CHAR | Type::NAME | Type::BPCHAR => row
-                .get::<_, Option<String>>(i)
-                .map(serde_json::Value::String)
-                .unwrap_or(serde_json::Value::Null),
-            Type::UUID => row
-                .get::<_, Option<uuid::Uuid>>(i)
-                .map(|v| serde_json::Value::String(v.to_string()))
-                .unwrap_or(serde_json::Value::Null),
-            Type::JSON | Type::JSONB => row
-                .get::<_, Option<serde_json::Value>>(i)
-                .unwrap_or(serde_json::Value::Null),
-            Type::TIMESTAMP => row
-                .get::<_, Option<chrono::NaiveDateTime>>(i)
-                .map(|v| serde_json::Value::String(v.to_string()))
-                .unwrap_or(serde_json::Value::Null),
-            Type::TIMESTAMPTZ => row
-                .get::<_, Option<chrono::DateTime<chrono::Utc>>>(i)
-                .map(|v| serde_json::Value::String(v.to_rfc3339()))
-                .unwrap_or(serde_json::Value::Null),
-            Type::BYTEA => row
-                .get::<_, Option<Vec<u8>>>(i)
-                .map(|v| {
-                    let hex: String = v.iter().map(|b| format!("{:02x}", b)).collect();
-                    serde_json::Value::String(format!("\\x{}", hex))
-                })
-                .unwrap_or(serde_json::Value::Null),
-            // Fallback: try to get as string
-            _ => row
-                .get::<_, Option<String>>(i)
-                .map(serde_json::Value::String)
-                .unwrap_or(serde_json::Value::Null),
-        };
-        values.push(value);
-    }
-    values
+    row.columns()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| match row.try_get::<_, RawValue>(i) {
+            Ok(RawValue(Some(raw))) => pg_decode::decode(column.type_(), raw),
+            _ => serde_json::Value::Null,
+        })
+        .collect()
 }
 
 // ── Save (upsert) ──────────────────────────────────────────────────────
@@ -146,6 +175,10 @@ pub async fn pg_save_connection(
     username: String,
     password: Option<String>,
     ssl_mode: String,
+    ssl_ca_secret_id: Option<String>,
+    ssl_cert_secret_id: Option<String>,
+    ssl_key_secret_id: Option<String>,
+    statement_timeout_ms: Option<i64>,
     color: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<PgConnection, String> {
@@ -166,7 +199,7 @@ pub async fn pg_save_connection(
             if let Some(ref pw) = password {
                 let encrypted = crate::crypto::secure_encrypt(pw.as_bytes(), &key)?;
                 sqlx::query(
-                    "UPDATE pg_connections SET label = ?, forward_id = ?, favorite_id = ?, host = ?, port = ?, database_name = ?, username = ?, password = ?, ssl_mode = ?, color = ? WHERE id = ?"
+                    "UPDATE pg_connections SET label = ?, forward_id = ?, favorite_id = ?, host = ?, port = ?, database_name = ?, username = ?, password = ?, ssl_mode = ?, ssl_ca_secret_id = ?, ssl_cert_secret_id = ?, ssl_key_secret_id = ?, statement_timeout_ms = ?, color = ? WHERE id = ?"
                 )
                 .bind(&label)
                 .bind(&forward_id)
@@ -177,6 +210,10 @@ pub async fn pg_save_connection(
                 .bind(&username)
                 .bind(&encrypted)
                 .bind(&ssl_mode)
+                .bind(&ssl_ca_secret_id)
+                .bind(&ssl_cert_secret_id)
+                .bind(&ssl_key_secret_id)
+                .bind(statement_timeout_ms)
                 .bind(&color)
                 .bind(existing_id)
                 .execute(&state.db)
@@ -185,7 +222,7 @@ pub async fn pg_save_connection(
             } else {
                 // No password provided — don't update password field
                 sqlx::query(
-                    "UPDATE pg_connections SET label = ?, forward_id = ?, favorite_id = ?, host = ?, port = ?, database_name = ?, username = ?, ssl_mode = ?, color = ? WHERE id = ?"
+                    "UPDATE pg_connections SET label = ?, forward_id = ?, favorite_id = ?, host = ?, port = ?, database_name = ?, username = ?, ssl_mode = ?, ssl_ca_secret_id = ?, ssl_cert_secret_id = ?, ssl_key_secret_id = ?, statement_timeout_ms = ?, color = ? WHERE id = ?"
                 )
                 .bind(&label)
                 .bind(&forward_id)
@@ -195,6 +232,10 @@ pub async fn pg_save_connection(
                 .bind(&database_name)
                 .bind(&username)
                 .bind(&ssl_mode)
+                .bind(&ssl_ca_secret_id)
+                .bind(&ssl_cert_secret_id)
+                .bind(&ssl_key_secret_id)
+                .bind(statement_timeout_ms)
                 .bind(&color)
                 .bind(existing_id)
                 .execute(&state.db)
@@ -211,7 +252,7 @@ pub async fn pg_save_connection(
                 .transpose()?;
 
             sqlx::query(
-                "INSERT INTO pg_connections (id, label, forward_id, favorite_id, host, port, database_name, username, password, ssl_mode, color) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+                "INSERT INTO pg_connections (id, label, forward_id, favorite_id, host, port, database_name, username, password, ssl_mode, ssl_ca_secret_id, ssl_cert_secret_id, ssl_key_secret_id, statement_timeout_ms, color) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
             )
             .bind(existing_id)
             .bind(&label)
@@ -223,6 +264,10 @@ pub async fn pg_save_connection(
             .bind(&username)
             .bind(&encrypted)
             .bind(&ssl_mode)
+            .bind(&ssl_ca_secret_id)
+            .bind(&ssl_cert_secret_id)
+            .bind(&ssl_key_secret_id)
+            .bind(statement_timeout_ms)
             .bind(&color)
             .execute(&state.db)
             .await
@@ -239,7 +284,7 @@ pub async fn pg_save_connection(
             .transpose()?;
 
         sqlx::query(
-            "INSERT INTO pg_connections (id, label, forward_id, favorite_id, host, port, database_name, username, password, ssl_mode, color) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            "INSERT INTO pg_connections (id, label, forward_id, favorite_id, host, port, database_name, username, password, ssl_mode, ssl_ca_secret_id, ssl_cert_secret_id, ssl_key_secret_id, statement_timeout_ms, color) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&new_id)
         .bind(&label)
@@ -251,6 +296,10 @@ pub async fn pg_save_connection(
         .bind(&username)
         .bind(&encrypted)
         .bind(&ssl_mode)
+        .bind(&ssl_ca_secret_id)
+        .bind(&ssl_cert_secret_id)
+        .bind(&ssl_key_secret_id)
+        .bind(statement_timeout_ms)
         .bind(&color)
         .execute(&state.db)
         .await
@@ -259,9 +308,14 @@ pub async fn pg_save_connection(
         new_id
     };
 
+    // A connected pool picks up the new timeout on its next checkouts
+    if let Some(pool) = state.pg_pools.lock().await.get_mut(&conn_id) {
+        pool.statement_timeout_ms = statement_timeout_ms;
+    }
+
     // Return the saved connection
     let row: PgConnection = sqlx::query_as(
-        "SELECT id, label, forward_id, favorite_id, host, port, database_name, username, password, ssl_mode, color, created_at, last_used FROM pg_connections WHERE id = ?"
+        "SELECT id, label, forward_id, favorite_id, host, port, database_name, username, password, ssl_mode, ssl_ca_secret_id, ssl_cert_secret_id, ssl_key_secret_id, statement_timeout_ms, color, created_at, last_used FROM pg_connections WHERE id = ?"
     )
     .bind(&conn_id)
     .fetch_one(&state.db)
@@ -278,7 +332,7 @@ pub async fn pg_list_connections(
     state: State<'_, AppState>,
 ) -> Result<Vec<PgConnection>, String> {
     let connections: Vec<PgConnection> = sqlx::query_as(
-        "SELECT id, label, forward_id, favorite_id, host, port, database_name, username, password, ssl_mode, color, created_at, last_used FROM pg_connections ORDER BY created_at DESC"
+        "SELECT id, label, forward_id, favorite_id, host, port, database_name, username, password, ssl_mode, ssl_ca_secret_id, ssl_cert_secret_id, ssl_key_secret_id, statement_timeout_ms, color, created_at, last_used FROM pg_connections ORDER BY created_at DESC"
     )
     .fetch_all(&state.db)
     .await
@@ -299,6 +353,9 @@ pub async fn pg_delete_connection(
         let mut pools = state.pg_pools.lock().await;
         pools.remove(&id);
     }
+    state.pg_tls.lock().await.remove(&id);
+    state.pg_cursors.lock().await.retain(|_, c| c.connection_id != id);
+    abandon_session(&state, &id).await;
 
     sqlx::query("DELETE FROM pg_connections WHERE id = ?")
         .bind(&id)
@@ -311,14 +368,30 @@ pub async fn pg_delete_connection(
 
 // ── Test connection ─────────────────────────────────────────────────────
 
+/// Try connecting with unsaved settings. `ssl_mode` defaults to `prefer`;
+/// the CA, client certificate and key are vault secret IDs.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn pg_test_connection(
     host: String,
     port: i64,
     database_name: String,
     username: String,
     password: String,
+    ssl_mode: Option<String>,
+    ssl_ca_secret_id: Option<String>,
+    ssl_cert_secret_id: Option<String>,
+    ssl_key_secret_id: Option<String>,
+    vault_state: State<'_, VaultState>,
 ) -> Result<String, String> {
+    let tls = PgTls::from_vault(
+        ssl_mode.as_deref().unwrap_or("prefer"),
+        ssl_ca_secret_id.as_deref(),
+        ssl_cert_secret_id.as_deref(),
+        ssl_key_secret_id.as_deref(),
+        &vault_state,
+    )?;
+
     let mut config = tokio_postgres::Config::new();
     config.host(&host);
     config.port(port as u16);
@@ -326,20 +399,7 @@ pub async fn pg_test_connection(
     config.user(&username);
     config.password(&password);
 
-    let (client, connection) =
-        tokio_postgres::connect(&format!(
-            "host={} port={} dbname={} user={} password={}",
-            host, port, database_name, username, password
-        ), tokio_postgres::NoTls)
-        .await
-        .map_err(|e| format!("Connection failed: {e}"))?;
-
-    // Spawn the connection handler so the client can work
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            log::error!("pg test connection error: {e}");
-        }
-    });
+    let client = tls.connect(&mut config).await?;
 
     // Run a simple query to verify it works
     client
@@ -354,61 +414,296 @@ pub async fn pg_test_connection(
 
 // ── Connect (create pool) ───────────────────────────────────────────────
 
-#[tauri::command]
-pub async fn pg_connect(
-    id: String,
-    state: State<'_, AppState>,
-) -> Result<String, String> {
-    // Load connection details from DB
-    let conn: PgConnection = sqlx::query_as(
-        "SELECT id, label, forward_id, favorite_id, host, port, database_name, username, password, ssl_mode, color, created_at, last_used FROM pg_connections WHERE id = ?"
+/// How long to wait for an auto-started port forward to start listening.
+const FORWARD_READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+async fn load_connection(id: &str, db: &SqlitePool) -> Result<PgConnection, String> {
+    sqlx::query_as(
+        "SELECT id, label, forward_id, favorite_id, host, port, database_name, username, password, ssl_mode, ssl_ca_secret_id, ssl_cert_secret_id, ssl_key_secret_id, statement_timeout_ms, color, created_at, last_used FROM pg_connections WHERE id = ?"
     )
-    .bind(&id)
-    .fetch_one(&state.db)
+    .bind(id)
+    .fetch_one(db)
     .await
-    .map_err(|e| format!("Connection profile not found: {e}"))?;
-
-    // Decrypt password
-    let password = if let Some(ref encrypted) = conn.password {
-        let key = crate::crypto::get_encryption_key()?;
-        let (decrypted, needs_migration) = crate::crypto::secure_decrypt(encrypted, &key)?;
-        let pw = String::from_utf8(decrypted)
-            .map_err(|_| {
-                "Failed to decode password. The encryption key may have changed — \
-                 please re-save this connection's credentials."
-                    .to_string()
-            })?;
-
-        // Transparently migrate legacy XOR data to AES-256-GCM
-        if needs_migration {
-            if let Ok(re_encrypted) = crate::crypto::secure_encrypt(pw.as_bytes(), &key) {
-                let _ = sqlx::query("UPDATE pg_connections SET password = ? WHERE id = ?")
-                    .bind(&re_encrypted)
-                    .bind(&id)
-                    .execute(&state.db)
-                    .await;
-            }
-        }
+    .map_err(|e| format!("Connection profile not found: {e}"))
+}
 
-        pw
-    } else {
-        String::new()
+/// Decrypt a saved connection's password, migrating legacy ciphertext.
+async fn decrypt_password(conn: &PgConnection, db: &SqlitePool) -> Result<String, String> {
+    let Some(ref encrypted) = conn.password else {
+        return Ok(String::new());
     };
 
-    // Build deadpool config
+    let key = crate::crypto::get_encryption_key()?;
+    let (decrypted, needs_migration) = crate::crypto::secure_decrypt(encrypted, &key)?;
+    let pw = String::from_utf8(decrypted)
+        .map_err(|_| {
+            "Failed to decode password. The encryption key may have changed — \
+             please re-save this connection's credentials."
+                .to_string()
+        })?;
+
+    // Transparently migrate legacy XOR data to AES-256-GCM
+    if needs_migration {
+        if let Ok(re_encrypted) = crate::crypto::secure_encrypt(pw.as_bytes(), &key) {
+            let _ = sqlx::query("UPDATE pg_connections SET password = ? WHERE id = ?")
+                .bind(&re_encrypted)
+                .bind(&conn.id)
+                .execute(db)
+                .await;
+        }
+    }
+
+    Ok(pw)
+}
+
+/// Clients per connection pool.
+const POOL_SIZE: usize = 8;
+
+/// How long a command waits for a free pooled client before giving up.
+const POOL_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A connected pool with the connection's `statement_timeout_ms`, read when
+/// it connected and kept current by `pg_save_connection`.
+#[derive(Clone)]
+pub struct PgPool {
+    pool: deadpool_postgres::Pool,
+    statement_timeout_ms: Option<i64>,
+    /// Timeout each client was last set to. A client without an entry has
+    /// an unknown value and is set on its next checkout.
+    applied: Arc<std::sync::Mutex<HashMap<ObjectId, Option<i64>>>>,
+}
+
+impl PgPool {
+    async fn get(&self) -> Result<deadpool_postgres::Object, String> {
+        self.pool.get().await.map_err(|e| match e {
+            PoolError::Timeout(_) => format!(
+                "All {} connections are busy; close open results or cancel running queries",
+                self.pool.status().max_size
+            ),
+            e => format!("Pool error: {e}"),
+        })
+    }
+}
+
+/// Build a pool for a saved connection at `host:port`. The pool connects
+/// lazily; callers that need a live connection must check one out.
+async fn create_pool(
+    conn: &PgConnection,
+    host: &str,
+    port: u16,
+    tls: &PgTls,
+    db: &SqlitePool,
+) -> Result<PgPool, String> {
+    let password = decrypt_password(conn, db).await?;
+
     let mut cfg = deadpool_postgres::Config::new();
-    cfg.host = Some(conn.host.clone());
-    cfg.port = Some(conn.port as u16);
+    cfg.host = Some(host.to_string());
+    cfg.port = Some(port);
     cfg.dbname = Some(conn.database_name.clone());
     cfg.user = Some(conn.username.clone());
     cfg.password = Some(password);
+    cfg.pool = Some(PoolConfig {
+        timeouts: Timeouts {
+            wait: Some(POOL_WAIT_TIMEOUT),
+            ..Timeouts::default()
+        },
+        ..PoolConfig::new(POOL_SIZE)
+    });
+
+    Ok(PgPool {
+        pool: tls.create_pool(&mut cfg)?,
+        statement_timeout_ms: conn.statement_timeout_ms,
+        applied: Arc::default(),
+    })
+}
+
+/// Start the forward a connection is bound to: its favorite if it has one,
+/// otherwise a restart of its last forward. `None` when neither exists.
+async fn start_bound_forward(
+    conn: &PgConnection,
+    state: &State<'_, AppState>,
+) -> Result<Option<ActiveForward>, String> {
+    if let Some(ref favorite_id) = conn.favorite_id {
+        let favorite: Option<Favorite> = sqlx::query_as(
+            "SELECT id, kubeconfig_id, namespace, resource_type, resource_name, remote_port, local_port, label, group_name FROM favorites WHERE id = ?"
+        )
+        .bind(favorite_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        if let Some(fav) = favorite {
+            let forward = create_forward(
+                fav.kubeconfig_id,
+                fav.namespace,
+                fav.resource_type,
+                fav.resource_name,
+                fav.remote_port,
+                fav.local_port,
+                Some(fav.id),
+                state.clone(),
+            )
+            .await?;
+            return Ok(Some(forward));
+        }
+    }
 
-    let pool = cfg
-        .create_pool(Some(deadpool_postgres::Runtime::Tokio1), tokio_postgres::NoTls)
-        .map_err(|e| format!("Failed to create pool: {e}"))?;
+    if let Some(ref forward_id) = conn.forward_id {
+        let exists: Option<(String,)> = sqlx::query_as("SELECT id FROM active_forwards WHERE id = ?")
+            .bind(forward_id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if exists.is_some() {
+            let forward = restart_forward(forward_id.clone(), state.clone()).await?;
+            return Ok(Some(forward));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Where to reach a saved connection right now.
+///
+/// Connections bound to a forward or favorite use the forward's current
+/// local port, starting the forward first if it is not running. Unbound
+/// connections, and bound ones whose forward and favorite are both gone,
+/// use the stored host and port.
+async fn resolve_endpoint(
+    conn: &PgConnection,
+    state: &State<'_, AppState>,
+) -> Result<(String, u16), String> {
+    let stored = (conn.host.clone(), conn.port as u16);
+    if conn.forward_id.is_none() && conn.favorite_id.is_none() {
+        return Ok(stored);
+    }
+
+    let running = find_running_forward(
+        &state.db,
+        conn.forward_id.as_deref(),
+        conn.favorite_id.as_deref(),
+    )
+    .await?;
+    let forward = match running {
+        Some(forward) => forward,
+        None => match start_bound_forward(conn, state).await? {
+            Some(forward) => forward,
+            None => {
+                log::warn!(
+                    "Forward for pg connection {} no longer exists, using stored address",
+                    conn.id
+                );
+                return Ok(stored);
+            }
+        },
+    };
+
+    let port = forward.local_port as u16;
+    if !wait_for_local_port(port, FORWARD_READY_TIMEOUT).await {
+        return Err(format!(
+            "Port forward to {}/{} is not accepting connections on port {port}",
+            forward.resource_type, forward.resource_name
+        ));
+    }
+
+    if conn.forward_id.as_deref() != Some(forward.id.as_str()) || conn.port != forward.local_port {
+        sqlx::query("UPDATE pg_connections SET forward_id = ?, host = '127.0.0.1', port = ? WHERE id = ?")
+            .bind(&forward.id)
+            .bind(forward.local_port)
+            .bind(&conn.id)
+            .execute(&state.db)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(("127.0.0.1".to_string(), port))
+}
+
+/// Point the saved connections bound to `forward` (directly or through its
+/// favorite) at its local port, and rebuild the pools of the ones currently
+/// connected on a different port. Best-effort: failures are logged.
+pub async fn rebind_connections(state: &AppState, forward: &ActiveForward) {
+    let bound: Vec<PgConnection> = match sqlx::query_as(
+        "SELECT id, label, forward_id, favorite_id, host, port, database_name, username, password, ssl_mode, ssl_ca_secret_id, ssl_cert_secret_id, ssl_key_secret_id, statement_timeout_ms, color, created_at, last_used FROM pg_connections \
+         WHERE forward_id = ? OR (favorite_id IS NOT NULL AND favorite_id = ?)"
+    )
+    .bind(&forward.id)
+    .bind(&forward.favorite_id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            log::error!("Failed to load connections bound to forward {}: {e}", forward.id);
+            return;
+        }
+    };
+
+    for conn in bound {
+        if let Err(e) = sqlx::query("UPDATE pg_connections SET forward_id = ?, host = '127.0.0.1', port = ? WHERE id = ?")
+            .bind(&forward.id)
+            .bind(forward.local_port)
+            .bind(&conn.id)
+            .execute(&state.db)
+            .await
+        {
+            log::error!("Failed to rebind pg connection {}: {e}", conn.id);
+            continue;
+        }
+
+        if conn.port == forward.local_port && conn.host == "127.0.0.1" {
+            continue;
+        }
+
+        let mut pools = state.pg_pools.lock().await;
+        if !pools.contains_key(&conn.id) {
+            continue;
+        }
+        // Reuse the TLS settings the pool was connected with; the vault may
+        // be locked by now.
+        let Some(tls) = state.pg_tls.lock().await.get(&conn.id).cloned() else {
+            pools.remove(&conn.id);
+            continue;
+        };
+        match create_pool(&conn, "127.0.0.1", forward.local_port as u16, &tls, &state.db).await {
+            Ok(pool) => {
+                pools.insert(conn.id.clone(), pool);
+            }
+            Err(e) => {
+                log::error!("Failed to rebuild pool for pg connection {}: {e}", conn.id);
+                pools.remove(&conn.id);
+            }
+        }
+    }
+}
+
+/// Connect a saved connection and keep its pool until `pg_disconnect`.
+///
+/// Connections bound to a forward or favorite follow the forward's current
+/// local port and start the forward if it is stopped. TLS follows the
+/// connection's `ssl_mode`; a configured CA or client certificate is read
+/// from the vault, which must then be unlocked.
+#[tauri::command]
+pub async fn pg_connect(
+    id: String,
+    state: State<'_, AppState>,
+    vault_state: State<'_, VaultState>,
+) -> Result<String, String> {
+    let conn = load_connection(&id, &state.db).await?;
+    let tls = PgTls::from_vault(
+        &conn.ssl_mode,
+        conn.ssl_ca_secret_id.as_deref(),
+        conn.ssl_cert_secret_id.as_deref(),
+        conn.ssl_key_secret_id.as_deref(),
+        &vault_state,
+    )?;
+    let (host, port) = resolve_endpoint(&conn, &state).await?;
+    let pool = create_pool(&conn, &host, port, &tls, &state.db).await?;
 
     // Verify the pool actually connects
     let _client = pool
+        .pool
         .get()
         .await
         .map_err(|e| format!("Failed to connect: {e}"))?;
@@ -418,6 +713,7 @@ pub async fn pg_connect(
         let mut pools = state.pg_pools.lock().await;
         pools.insert(id.clone(), pool);
     }
+    state.pg_tls.lock().await.insert(id.clone(), tls);
 
     // Update last_used timestamp
     let now = chrono::Utc::now().to_rfc3339();
@@ -434,12 +730,19 @@ pub async fn pg_connect(
 
 // ── Disconnect ──────────────────────────────────────────────────────────
 
+/// Close a connection's pool. An open transaction is rolled back.
 #[tauri::command]
 pub async fn pg_disconnect(
     id: String,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
+    if abandon_session(&state, &id).await {
+        log::info!("Rolled back the open transaction on {id} before disconnecting");
+    }
+
     let mut pools = state.pg_pools.lock().await;
+    state.pg_tls.lock().await.remove(&id);
+    state.pg_cursors.lock().await.retain(|_, c| c.connection_id != id);
     if pools.remove(&id).is_none() {
         return Err(format!("No active connection for id {id}"));
     }
@@ -509,9 +812,7 @@ pub async fn pg_list_schemas(
     id: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<String>, String> {
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+    let client = pooled_client(&id, &state).await?;
 
     let rows = client
         .query(
@@ -533,9 +834,7 @@ pub async fn pg_list_tables(
     schema: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<PgTableInfo>, String> {
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+    let client = pooled_client(&id, &state).await?;
 
     let rows = client
         .query(
@@ -576,9 +875,7 @@ pub async fn pg_list_columns(
     table: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<PgColumnInfo>, String> {
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+    let client = pooled_client(&id, &state).await?;
 
     let rows = client
         .query(
@@ -630,9 +927,7 @@ pub async fn pg_list_indexes(
     table: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<PgIndexInfo>, String> {
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+    let client = pooled_client(&id, &state).await?;
 
     let rows = client
         .query(
@@ -682,9 +977,7 @@ pub async fn pg_table_row_count(
         return Err("Invalid table name".to_string());
     }
 
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+    let client = pooled_client(&id, &state).await?;
 
     let query = format!(
         "SELECT count(*) FROM \"{}\".\"{}\"",
@@ -702,244 +995,1139 @@ pub async fn pg_table_row_count(
 
 // ── Query Execution ────────────────────────────────────────────────────
 
-#[tauri::command]
-pub async fn pg_execute_query(
-    id: String,
-    sql: String,
-    page: Option<i64>,
-    page_size: Option<i64>,
-    state: State<'_, AppState>,
-) -> Result<PgQueryResult, String> {
-    let page = page.unwrap_or(0);
-    let page_size = page_size.unwrap_or(100);
-
-    // Detect query type
-    let trimmed = sql.trim().to_uppercase();
-    let query_type = if trimmed.starts_with("SELECT")
-        || trimmed.starts_with("WITH")
-        || trimmed.starts_with("EXPLAIN")
-    {
-        "SELECT"
-    } else if trimmed.starts_with("INSERT") {
-        "INSERT"
-    } else if trimmed.starts_with("UPDATE") {
-        "UPDATE"
-    } else if trimmed.starts_with("DELETE") {
-        "DELETE"
-    } else {
-        "DDL"
-    };
+/// A query registered for cancellation while it executes.
+pub struct RunningQuery {
+    connection_id: String,
+    cancel_token: CancelToken,
+}
+
+/// The server's message for a failed query, e.g. "canceling statement due
+/// to user request". `tokio_postgres::Error`'s `Display` only says "db error".
+fn query_error(e: &tokio_postgres::Error) -> String {
+    match e.as_db_error() {
+        Some(db) => db.message().to_string(),
+        None => e.to_string(),
+    }
+}
+
+/// A transaction pinned to one client, opened with `pg_begin` or a `BEGIN`
+/// run from the editor and ended with `pg_commit`/`pg_rollback` or a
+/// `COMMIT`/`ROLLBACK` from the editor.
+pub struct PgSession {
+    client: deadpool_postgres::Object,
+    started_at: chrono::DateTime<chrono::Utc>,
+    last_activity: std::sync::Mutex<Instant>,
+    running: AtomicUsize,
+    failed: AtomicBool,
+}
 
-    let is_select = query_type == "SELECT";
+#[derive(Debug, Serialize)]
+pub struct PgTransactionStatus {
+    pub started_at: String,
+    /// Milliseconds since the last statement in the transaction finished;
+    /// zero while one is running.
+    pub idle_ms: u64,
+    /// The last statement failed, so the server ignores everything until
+    /// the transaction (or a savepoint) is rolled back.
+    pub failed: bool,
+}
 
-    // Get pool/client
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+impl PgSession {
+    fn new(client: deadpool_postgres::Object) -> Self {
+        Self {
+            client,
+            started_at: chrono::Utc::now(),
+            last_activity: std::sync::Mutex::new(Instant::now()),
+            running: AtomicUsize::new(0),
+            failed: AtomicBool::new(false),
+        }
+    }
 
-    // Drop the lock before executing the query
-    drop(pools);
+    fn enter(&self) {
+        self.running.fetch_add(1, Ordering::SeqCst);
+    }
 
-    let start = Instant::now();
+    /// Record a statement finishing. In an aborted transaction only a
+    /// rollback succeeds, so the outcome of the last statement tells
+    /// whether the transaction is usable.
+    fn leave(&self, ok: bool) {
+        self.failed.store(!ok, Ordering::SeqCst);
+        if let Ok(mut last) = self.last_activity.lock() {
+            *last = Instant::now();
+        }
+        self.running.fetch_sub(1, Ordering::SeqCst);
+    }
 
-    let result = if is_select {
-        // Get total count (may fail for complex queries)
-        let count_sql = format!("SELECT count(*) FROM ({}) AS _count_subquery", sql);
-        let total_rows = match client.query_one(&count_sql, &[]).await {
-            Ok(row) => Some(row.get::<_, i64>(0)),
-            Err(_) => None,
+    fn status(&self) -> PgTransactionStatus {
+        let idle_ms = if self.running.load(Ordering::SeqCst) > 0 {
+            0
+        } else {
+            self.last_activity
+                .lock()
+                .map(|last| last.elapsed().as_millis() as u64)
+                .unwrap_or(0)
         };
+        PgTransactionStatus {
+            started_at: self.started_at.to_rfc3339(),
+            idle_ms,
+            failed: self.failed.load(Ordering::SeqCst),
+        }
+    }
+}
 
-        // Execute with pagination
-        let paged_sql = format!("{} LIMIT {} OFFSET {}", sql, page_size, page * page_size);
-        match client.query(&paged_sql, &[]).await {
-            Ok(rows) => {
-                let columns: Vec<PgColumnMeta> = if !rows.is_empty() {
-                    rows[0]
-                        .columns()
-                        .iter()
-                        .map(|c| PgColumnMeta {
-                            name: c.name().to_string(),
-                            data_type: c.type_().name().to_string(),
-                        })
-                        .collect()
-                } else {
-                    Vec::new()
-                };
-
-                let row_count = rows.len() as i64;
-                let json_rows: Vec<Vec<serde_json::Value>> =
-                    rows.iter().map(|r| row_to_json(r)).collect();
-
-                let duration_ms = start.elapsed().as_millis() as u64;
-
-                // Record success in history
-                let history_id = uuid::Uuid::new_v4().to_string();
-                let _ = sqlx::query(
-                    "INSERT INTO pg_query_history (id, connection_id, sql_text, duration_ms, row_count, error) VALUES (?, ?, ?, ?, ?, ?)"
-                )
-                .bind(&history_id)
-                .bind(&id)
-                .bind(&sql)
-                .bind(duration_ms as i64)
-                .bind(row_count)
-                .bind::<Option<&str>>(None)
-                .execute(&state.db)
-                .await;
+/// A client for user SQL: the connection's open transaction if it has one,
+/// otherwise a client from the pool.
+enum QueryClient {
+    Pooled(Box<deadpool_postgres::Object>),
+    Session(Arc<PgSession>),
+}
 
-                Ok(PgQueryResult {
-                    columns,
-                    rows: json_rows,
-                    total_rows,
-                    affected_rows: None,
-                    duration_ms,
-                    query_type: query_type.to_string(),
-                })
-            }
-            Err(e) => {
-                let error_str = e.to_string();
-                let duration_ms = start.elapsed().as_millis() as u64;
+impl std::ops::Deref for QueryClient {
+    type Target = tokio_postgres::Client;
 
-                // Record error in history
-                let history_id = uuid::Uuid::new_v4().to_string();
-                let _ = sqlx::query(
-                    "INSERT INTO pg_query_history (id, connection_id, sql_text, duration_ms, row_count, error) VALUES (?, ?, ?, ?, ?, ?)"
-                )
-                .bind(&history_id)
-                .bind(&id)
-                .bind(&sql)
-                .bind(duration_ms as i64)
-                .bind::<Option<i64>>(None)
-                .bind(&error_str)
-                .execute(&state.db)
-                .await;
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Pooled(client) => client,
+            Self::Session(session) => &session.client,
+        }
+    }
+}
 
-                Err(format!("Query failed: {e}"))
-            }
+impl QueryClient {
+    fn session(&self) -> Option<&Arc<PgSession>> {
+        match self {
+            Self::Pooled(_) => None,
+            Self::Session(session) => Some(session),
         }
-    } else {
-        // DML/DDL
-        match client.execute(sql.as_str(), &[]).await {
-            Ok(affected) => {
-                let row_count = affected as i64;
-                let duration_ms = start.elapsed().as_millis() as u64;
-
-                // Record success in history
-                let history_id = uuid::Uuid::new_v4().to_string();
-                let _ = sqlx::query(
-                    "INSERT INTO pg_query_history (id, connection_id, sql_text, duration_ms, row_count, error) VALUES (?, ?, ?, ?, ?, ?)"
-                )
-                .bind(&history_id)
-                .bind(&id)
-                .bind(&sql)
-                .bind(duration_ms as i64)
-                .bind(row_count)
-                .bind::<Option<&str>>(None)
-                .execute(&state.db)
-                .await;
+    }
 
-                Ok(PgQueryResult {
-                    columns: Vec::new(),
-                    rows: Vec::new(),
-                    total_rows: None,
-                    affected_rows: Some(affected),
-                    duration_ms,
-                    query_type: query_type.to_string(),
-                })
-            }
-            Err(e) => {
-                let error_str = e.to_string();
-                let duration_ms = start.elapsed().as_millis() as u64;
+    fn object_id(&self) -> ObjectId {
+        match self {
+            Self::Pooled(client) => deadpool_postgres::Object::id(client),
+            Self::Session(session) => deadpool_postgres::Object::id(&session.client),
+        }
+    }
+}
 
-                // Record error in history
-                let history_id = uuid::Uuid::new_v4().to_string();
-                let _ = sqlx::query(
-                    "INSERT INTO pg_query_history (id, connection_id, sql_text, duration_ms, row_count, error) VALUES (?, ?, ?, ?, ?, ?)"
-                )
-                .bind(&history_id)
-                .bind(&id)
-                .bind(&sql)
-                .bind(duration_ms as i64)
-                .bind::<Option<i64>>(None)
-                .bind(&error_str)
-                .execute(&state.db)
-                .await;
+/// Statements that open a transaction block.
+fn begins_transaction(statement: &str) -> bool {
+    matches!(first_keyword(statement).as_str(), "BEGIN" | "START")
+}
 
-                Err(format!("Query failed: {e}"))
-            }
+/// Statements that end a transaction block. `ROLLBACK TO` a savepoint and
+/// the `AND CHAIN` forms keep it open.
+fn ends_transaction(statement: &str) -> bool {
+    let upper = statement.to_uppercase();
+    let words: Vec<&str> = upper.split_whitespace().collect();
+    match first_keyword(statement).as_str() {
+        "COMMIT" | "END" | "ABORT" | "ROLLBACK" => {
+            !words.contains(&"TO") && !upper.contains("AND CHAIN")
         }
+        "PREPARE" => words.get(1) == Some(&"TRANSACTION"),
+        _ => false,
+    }
+}
+
+/// Check out a client for running user SQL: the open transaction's client
+/// if there is one, otherwise a pooled client from `checkout_pooled`.
+async fn checkout(id: &str, state: &AppState) -> Result<QueryClient, String> {
+    if let Some(session) = state.pg_sessions.lock().await.get(id) {
+        return Ok(QueryClient::Session(session.clone()));
+    }
+    checkout_pooled(id, state)
+        .await
+        .map(|client| QueryClient::Pooled(Box::new(client)))
+}
+
+/// A connection's pool, cloned out of `pg_pools` so a caller waiting on a
+/// busy pool does not hold the map lock other commands need.
+async fn connected_pool(id: &str, state: &AppState) -> Result<PgPool, String> {
+    state
+        .pg_pools
+        .lock()
+        .await
+        .get(id)
+        .cloned()
+        .ok_or_else(|| "Not connected".to_string())
+}
+
+/// Check out a client from a connection's pool.
+async fn pooled_client(id: &str, state: &AppState) -> Result<deadpool_postgres::Object, String> {
+    connected_pool(id, state).await?.get().await
+}
+
+/// Check out a pooled client, applying the connection's default
+/// `statement_timeout`. `SET` is only sent when the client's last known
+/// value differs, which is on its first checkout, after the saved timeout
+/// changes, or after user SQL touched the setting (see `forget_timeout`).
+async fn checkout_pooled(id: &str, state: &AppState) -> Result<deadpool_postgres::Object, String> {
+    let pool = connected_pool(id, state).await?;
+    let client = pool.get().await?;
+
+    let object = deadpool_postgres::Object::id(&client);
+    let timeout = pool.statement_timeout_ms;
+    if pool.applied.lock().unwrap().get(&object) == Some(&timeout) {
+        return Ok(client);
+    }
+    let set_timeout = match timeout {
+        Some(ms) => format!("SET statement_timeout = {ms}"),
+        None => "SET statement_timeout TO DEFAULT".to_string(),
     };
+    client
+        .batch_execute(&set_timeout)
+        .await
+        .map_err(|e| format!("Failed to set statement timeout: {e}"))?;
+    pool.applied.lock().unwrap().insert(object, timeout);
 
-    result
+    Ok(client)
 }
 
-// ── Query History ──────────────────────────────────────────────────────
+/// Mark `client`'s `statement_timeout` as unknown when `sql` may have
+/// changed it, so the next checkout sets it again.
+async fn forget_timeout(state: &AppState, id: &str, client: &QueryClient, sql: &str) {
+    if !sql.to_lowercase().contains("statement_timeout") {
+        return;
+    }
+    if let Ok(pool) = connected_pool(id, state).await {
+        pool.applied.lock().unwrap().remove(&client.object_id());
+    }
+}
 
-#[tauri::command]
-pub async fn pg_list_query_history(
-    connection_id: String,
-    limit: Option<i64>,
-    state: State<'_, AppState>,
-) -> Result<Vec<PgQueryHistoryEntry>, String> {
-    let limit = limit.unwrap_or(50);
+/// Rows returned per batch when the caller does not say.
+const DEFAULT_FETCH_SIZE: i64 = 100;
 
-    let entries: Vec<PgQueryHistoryEntry> = sqlx::query_as(
-        "SELECT id, connection_id, sql_text, executed_at, duration_ms, row_count, error \
-         FROM pg_query_history \
-         WHERE connection_id = ? \
-         ORDER BY executed_at DESC \
-         LIMIT ?",
-    )
-    .bind(&connection_id)
-    .bind(limit)
-    .fetch_all(&state.db)
-    .await
-    .map_err(|e| e.to_string())?;
+/// Cursor that paged results are read through. Every open cursor has its
+/// own pinned client and transaction, so one name is enough.
+const CURSOR_NAME: &str = "pm_cursor";
 
-    Ok(entries)
+/// A paged result held open between `pg_execute_query` and `pg_fetch_more`.
+///
+/// The client stays checked out of the pool with the cursor's transaction
+/// open until the last row is fetched, the query is closed, or newer
+/// cursors on the same connection push it out (see `park_cursor`).
+pub struct OpenCursor {
+    connection_id: String,
+    client: deadpool_postgres::Object,
+    columns: Vec<PgColumnMeta>,
+    fetched: i64,
+    last_used: Instant,
 }
 
-// ── Saved Queries ──────────────────────────────────────────────────────
+/// Cursors a connection keeps open at once. Kept well below `POOL_SIZE`
+/// so paged results never take every pooled client.
+const MAX_OPEN_CURSORS: usize = 4;
+
+/// Keep `cursor` open for `pg_fetch_more`. When its connection already has
+/// `MAX_OPEN_CURSORS` open, the least recently read ones are closed.
+async fn park_cursor(state: &AppState, query_id: String, mut cursor: OpenCursor) {
+    cursor.last_used = Instant::now();
+    let evicted: Vec<OpenCursor> = {
+        let mut cursors = state.pg_cursors.lock().await;
+        let mut open: Vec<(Instant, String)> = cursors
+            .iter()
+            .filter(|(id, c)| c.connection_id == cursor.connection_id && **id != query_id)
+            .map(|(id, c)| (c.last_used, id.clone()))
+            .collect();
+        open.sort();
+        let excess = (open.len() + 1).saturating_sub(MAX_OPEN_CURSORS);
+        let evicted = open
+            .into_iter()
+            .take(excess)
+            .filter_map(|(_, id)| cursors.remove(&id))
+            .collect();
+        cursors.insert(query_id, cursor);
+        evicted
+    };
+    for cursor in evicted {
+        finish_cursor(&cursor.client).await;
+    }
+}
 
-#[tauri::command]
-pub async fn pg_save_query(
-    connection_id: Option<String>,
-    label: String,
-    sql_text: String,
-    state: State<'_, AppState>,
-) -> Result<PgSavedQuery, String> {
-    let id = uuid::Uuid::new_v4().to_string();
+/// Rows produced by one round trip.
+struct Batch {
+    columns: Vec<PgColumnMeta>,
+    rows: Vec<Row>,
+    affected_rows: Option<u64>,
+    has_more: bool,
+}
 
-    sqlx::query(
-        "INSERT INTO pg_saved_queries (id, connection_id, label, sql_text) VALUES (?, ?, ?, ?)",
-    )
-    .bind(&id)
-    .bind(&connection_id)
-    .bind(&label)
-    .bind(&sql_text)
-    .execute(&state.db)
-    .await
-    .map_err(|e| e.to_string())?;
+fn column_meta(columns: &[tokio_postgres::Column]) -> Vec<PgColumnMeta> {
+    columns
+        .iter()
+        .map(|c| PgColumnMeta {
+            name: c.name().to_string(),
+            data_type: pg_decode::type_display_name(c.type_()),
+            type_oid: c.type_().oid(),
+            type_kind: pg_decode::type_kind(c.type_()).to_string(),
+        })
+        .collect()
+}
 
-    let saved: PgSavedQuery = sqlx::query_as(
-        "SELECT id, connection_id, label, sql_text, created_at FROM pg_saved_queries WHERE id = ?",
+/// Queries that can be read through a cursor. Anything else, including
+/// `EXPLAIN`, `SHOW` and `INSERT ... RETURNING`, runs directly.
+fn is_cursorable(sql: &str) -> bool {
+    matches!(
+        first_keyword(sql).as_str(),
+        "SELECT" | "WITH" | "VALUES" | "TABLE"
     )
-    .bind(&id)
-    .fetch_one(&state.db)
-    .await
-    .map_err(|e| e.to_string())?;
-
-    Ok(saved)
 }
 
-#[tauri::command]
+async fn record_history(
+    state: &AppState,
+    connection_id: &str,
+    sql: &str,
+    duration_ms: u64,
+    row_count: Option<i64>,
+    error: Option<&str>,
+) {
+    let history_id = uuid::Uuid::new_v4().to_string();
+    let _ = sqlx::query(
+        "INSERT INTO pg_query_history (id, connection_id, sql_text, duration_ms, row_count, error) VALUES (?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&history_id)
+    .bind(connection_id)
+    .bind(sql)
+    .bind(duration_ms as i64)
+    .bind(row_count)
+    .bind(error)
+    .execute(&state.db)
+    .await;
+}
+
+/// The planner's row estimate for `sql`, without running it.
+async fn estimate_rows(
+    client: &tokio_postgres::Client,
+    sql: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Option<i64> {
+    let row = client
+        .query_one(&format!("EXPLAIN (FORMAT JSON) {sql}"), params)
+        .await
+        .ok()?;
+    let plan: serde_json::Value = row.try_get(0).ok()?;
+    plan.get(0)?
+        .get("Plan")?
+        .get("Plan Rows")?
+        .as_f64()
+        .map(|rows| rows as i64)
+}
+
+/// Fetch the next `count` rows from the open cursor.
+async fn fetch_batch(
+    client: &tokio_postgres::Client,
+    count: i64,
+) -> Result<Batch, tokio_postgres::Error> {
+    let statement = client
+        .prepare(&format!("FETCH FORWARD {count} FROM {CURSOR_NAME}"))
+        .await?;
+    let rows = client.query(&statement, &[]).await?;
+    Ok(Batch {
+        columns: column_meta(statement.columns()),
+        has_more: rows.len() as i64 == count,
+        rows,
+        affected_rows: None,
+    })
+}
+
+/// Run a statement once, returning its rows if it has any.
+async fn run_direct(
+    client: &tokio_postgres::Client,
+    sql: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<Batch, tokio_postgres::Error> {
+    let statement = client.prepare(sql).await?;
+    if statement.columns().is_empty() {
+        let affected = client.execute(&statement, params).await?;
+        return Ok(Batch {
+            columns: Vec::new(),
+            rows: Vec::new(),
+            affected_rows: Some(affected),
+            has_more: false,
+        });
+    }
+
+    let rows = client.query(&statement, params).await?;
+    Ok(Batch {
+        columns: column_meta(statement.columns()),
+        affected_rows: Some(rows.len() as u64),
+        rows,
+        has_more: false,
+    })
+}
+
+/// Declare a cursor for `sql` and fetch the first batch. The transaction is
+/// left open only when more rows remain.
+///
+/// DECLARE only plans the query, so when it fails (a `WITH` containing
+/// data-modifying statements, `SELECT ... INTO`, or a plain syntax error)
+/// nothing has run yet and the query goes to `run_direct`, which either runs
+/// it or reports the error against the original SQL. A cancelled DECLARE is
+/// not retried.
+async fn open_cursor(
+    client: &tokio_postgres::Client,
+    sql: &str,
+    params: &[&(dyn ToSql + Sync)],
+    fetch_size: i64,
+) -> Result<Batch, tokio_postgres::Error> {
+    client.batch_execute("BEGIN").await?;
+
+    let declare = format!("DECLARE {CURSOR_NAME} NO SCROLL CURSOR FOR {sql}");
+    if let Err(e) = client.execute(&declare, params).await {
+        let _ = client.batch_execute("ROLLBACK").await;
+        if client.is_closed() || e.code() == Some(&SqlState::QUERY_CANCELED) {
+            return Err(e);
+        }
+        return run_direct(client, sql, params).await;
+    }
+
+    match fetch_batch(client, fetch_size).await {
+        Ok(batch) => {
+            if !batch.has_more {
+                client.batch_execute("COMMIT").await?;
+            }
+            Ok(batch)
+        }
+        Err(e) => {
+            let _ = client.batch_execute("ROLLBACK").await;
+            Err(e)
+        }
+    }
+}
+
+/// End a cursor's transaction so its client can go back to the pool.
+async fn finish_cursor(client: &tokio_postgres::Client) {
+    if client.batch_execute("COMMIT").await.is_err() {
+        let _ = client.batch_execute("ROLLBACK").await;
+    }
+}
+
+/// The one statement in `sql`, without its terminating semicolon.
+fn single_statement(sql: &str) -> Result<String, String> {
+    let mut statements = split_statements(sql);
+    match statements.len() {
+        0 => Err("Nothing to run".to_string()),
+        1 => Ok(statements.remove(0).text),
+        n => Err(format!("Query contains {n} statements; run it as a script")),
+    }
+}
+
+/// A statement ready to run with its converted parameter values.
+struct BoundQuery {
+    sql: String,
+    values: Vec<ParamValue>,
+}
+
+impl BoundQuery {
+    fn params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.values
+            .iter()
+            .map(|v| v.as_ref() as &(dyn ToSql + Sync))
+            .collect()
+    }
+}
+
+/// Number the statement's placeholders, let the server infer their types
+/// and convert `values` to match. Values for types without a native
+/// conversion are sent as text and cast in the statement.
+///
+/// `PREPARE` bodies refer to the prepared statement's own parameters, so
+/// their `$n` are left alone.
+async fn bind_query(
+    client: &tokio_postgres::Client,
+    statement: &str,
+    values: &[serde_json::Value],
+) -> Result<BoundQuery, String> {
+    let bound = if first_keyword(statement) == "PREPARE" {
+        None
+    } else {
+        Some(bind_placeholders(statement)?)
+    };
+    let bound = match bound {
+        Some(bound) if !bound.params.is_empty() || !values.is_empty() => bound,
+        _ => {
+            return Ok(BoundQuery {
+                sql: statement.to_string(),
+                values: Vec::new(),
+            })
+        }
+    };
+
+    let prepared = client
+        .prepare(&bound.text)
+        .await
+        .map_err(|e| format!("Query failed: {}", query_error(&e)))?;
+    let (values, casts) = bind_values(&bound.params, prepared.params(), values)?;
+    Ok(BoundQuery {
+        sql: cast_placeholders(&bound.text, &casts),
+        values,
+    })
+}
+
+/// Run user SQL.
+///
+/// Row-returning queries are read through a server-side cursor: the first
+/// `page_size` rows come back here and the rest through `pg_fetch_more`, so
+/// the query runs once however far the caller reads. Other statements run
+/// directly. With `estimate`, the planner's row count is included.
+///
+/// `$1` or `:name` placeholders are bound from `params`, in `$n` order
+/// (`:name` placeholders are numbered in order of first use, as reported by
+/// `pg_describe_query`).
+///
+/// `query_id` lets the caller cancel the query with `pg_cancel_query` while
+/// it runs; one is generated when omitted.
+#[tauri::command]
+pub async fn pg_execute_query(
+    id: String,
+    sql: String,
+    params: Option<Vec<serde_json::Value>>,
+    page_size: Option<i64>,
+    estimate: Option<bool>,
+    query_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<PgQueryResult, String> {
+    let fetch_size = page_size.unwrap_or(DEFAULT_FETCH_SIZE).max(1);
+
+    let statement = single_statement(&sql)?;
+    let body = statement.as_str();
+    let query_type = statement_type(body);
+
+    let client = checkout(&id, &state).await?;
+
+    // Registered before binding so a slow prepare can be cancelled too
+    let query_id = query_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    state.pg_running_queries.lock().await.insert(
+        query_id.clone(),
+        RunningQuery {
+            connection_id: id.clone(),
+            cancel_token: client.cancel_token(),
+        },
+    );
+
+    let bound = match bind_query(&client, body, &params.unwrap_or_default()).await {
+        Ok(bound) => bound,
+        Err(e) => {
+            state.pg_running_queries.lock().await.remove(&query_id);
+            record_history(&state, &id, &sql, 0, None, Some(&e)).await;
+            return Err(e);
+        }
+    };
+    let params = bound.params();
+
+    let start = Instant::now();
+
+    let cursorable = is_cursorable(body);
+    let estimated_rows = if cursorable && estimate.unwrap_or(false) {
+        estimate_rows(&client, &bound.sql, &params).await
+    } else {
+        None
+    };
+    // Inside an open transaction the cursor would end it, so rows are read
+    // directly and capped instead
+    let outcome = match client.session() {
+        Some(session) => {
+            session.enter();
+            let outcome = run_bounded(&client, &bound.sql, &params, fetch_size).await;
+            session.leave(outcome.is_ok());
+            outcome
+        }
+        None if cursorable => open_cursor(&client, &bound.sql, &params, fetch_size)
+            .await
+            .map(|b| (b, None)),
+        None => run_direct(&client, &bound.sql, &params).await.map(|b| (b, None)),
+    };
+
+    state.pg_running_queries.lock().await.remove(&query_id);
+    let duration_ms = start.elapsed().as_millis() as u64;
+    forget_timeout(&state, &id, &client, body).await;
+
+    if ends_transaction(body) {
+        if let Some(session) = client.session() {
+            release_session(&state, &id, session).await;
+        }
+    }
+
+    let (batch, exact_total) = match outcome {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            let error_str = query_error(&e);
+            record_history(&state, &id, &sql, duration_ms, None, Some(&error_str)).await;
+            return Err(format!("Query failed: {error_str}"));
+        }
+    };
+
+    let row_count = exact_total
+        .or(batch.affected_rows.map(|n| n as i64))
+        .unwrap_or(batch.rows.len() as i64);
+    record_history(&state, &id, &sql, duration_ms, Some(row_count), None).await;
+
+    let fetched = batch.rows.len() as i64;
+    let rows = batch.rows.iter().map(row_to_json).collect();
+    match client {
+        QueryClient::Pooled(client) if batch.has_more => {
+            park_cursor(
+                &state,
+                query_id.clone(),
+                OpenCursor {
+                    connection_id: id.clone(),
+                    client: *client,
+                    columns: batch.columns.clone(),
+                    fetched,
+                    last_used: Instant::now(),
+                },
+            )
+            .await;
+        }
+        // Keep the client a `BEGIN` ran on for the rest of the transaction
+        QueryClient::Pooled(client) if begins_transaction(body) => {
+            let session = Arc::new(PgSession::new(*client));
+            state.pg_sessions.lock().await.insert(id.clone(), session);
+        }
+        _ => {}
+    }
+
+    let is_rows = !batch.columns.is_empty();
+    Ok(PgQueryResult {
+        query_id,
+        total_rows: exact_total.or((is_rows && !batch.has_more).then_some(fetched)),
+        estimated_rows,
+        has_more: batch.has_more,
+        affected_rows: if is_rows { None } else { batch.affected_rows },
+        columns: batch.columns,
+        rows,
+        duration_ms,
+        query_type: query_type.to_string(),
+    })
+}
+
+/// Describe a statement without running it: its parameters, with the types
+/// the server infers for them, and the columns it returns.
+#[tauri::command]
+pub async fn pg_describe_query(
+    id: String,
+    sql: String,
+    state: State<'_, AppState>,
+) -> Result<PgQueryDescription, String> {
+    let statement = single_statement(&sql)?;
+    let bound = if first_keyword(&statement) == "PREPARE" {
+        None
+    } else {
+        Some(bind_placeholders(&statement)?)
+    };
+    let text = bound.as_ref().map_or(statement.as_str(), |b| b.text.as_str());
+
+    let client = checkout(&id, &state).await?;
+    let prepared = client
+        .prepare(text)
+        .await
+        .map_err(|e| format!("Failed to describe query: {}", query_error(&e)))?;
+
+    let names = bound.map(|b| b.params).unwrap_or_default();
+    let params = prepared
+        .params()
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| PgParamMeta {
+            name: names.get(i).cloned().unwrap_or_else(|| format!("${}", i + 1)),
+            data_type: pg_decode::type_display_name(ty),
+        })
+        .collect();
+
+    Ok(PgQueryDescription {
+        params,
+        columns: column_meta(prepared.columns()),
+    })
+}
+
+/// Fetch the next rows of a query left open by `pg_execute_query`. The
+/// cursor is closed once `has_more` comes back false.
+#[tauri::command]
+pub async fn pg_fetch_more(
+    query_id: String,
+    count: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<PgQueryResult, String> {
+    let count = count.unwrap_or(DEFAULT_FETCH_SIZE).max(1);
+    let mut cursor = state
+        .pg_cursors
+        .lock()
+        .await
+        .remove(&query_id)
+        .ok_or_else(|| format!("Query {query_id} is no longer open; run it again"))?;
+
+    state.pg_running_queries.lock().await.insert(
+        query_id.clone(),
+        RunningQuery {
+            connection_id: cursor.connection_id.clone(),
+            cancel_token: cursor.client.cancel_token(),
+        },
+    );
+    let start = Instant::now();
+    let outcome = fetch_batch(&cursor.client, count).await;
+    state.pg_running_queries.lock().await.remove(&query_id);
+
+    let batch = match outcome {
+        Ok(batch) => batch,
+        Err(e) => {
+            let _ = cursor.client.batch_execute("ROLLBACK").await;
+            return Err(format!("Fetch failed: {}", query_error(&e)));
+        }
+    };
+
+    cursor.fetched += batch.rows.len() as i64;
+    let total_rows = (!batch.has_more).then_some(cursor.fetched);
+    let result = PgQueryResult {
+        query_id: query_id.clone(),
+        columns: cursor.columns.clone(),
+        rows: batch.rows.iter().map(row_to_json).collect(),
+        total_rows,
+        estimated_rows: None,
+        has_more: batch.has_more,
+        affected_rows: None,
+        duration_ms: start.elapsed().as_millis() as u64,
+        query_type: "SELECT".to_string(),
+    };
+
+    if batch.has_more {
+        park_cursor(&state, query_id, cursor).await;
+    } else {
+        finish_cursor(&cursor.client).await;
+    }
+
+    Ok(result)
+}
+
+/// Close a query's cursor before all rows were fetched.
+#[tauri::command]
+pub async fn pg_close_query(
+    query_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let cursor = state.pg_cursors.lock().await.remove(&query_id);
+    if let Some(cursor) = cursor {
+        finish_cursor(&cursor.client).await;
+    }
+    Ok(())
+}
+
+// ── Scripts ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct PgStatementResult {
+    pub index: usize,
+    pub sql: String,
+    /// 1-based line of the script the statement starts on.
+    pub line: usize,
+    pub query_type: String,
+    /// `ok`, `error`, or `skipped` when an earlier error stopped the script.
+    pub status: String,
+    pub result: Option<PgQueryResult>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Run a statement once, keeping at most `max_rows` of its rows. The rest
+/// are read and counted so `total_rows` is exact.
+async fn run_bounded(
+    client: &tokio_postgres::Client,
+    sql: &str,
+    params: &[&(dyn ToSql + Sync)],
+    max_rows: i64,
+) -> Result<(Batch, Option<i64>), tokio_postgres::Error> {
+    let statement = client.prepare(sql).await?;
+    if statement.columns().is_empty() {
+        let affected = client.execute(&statement, params).await?;
+        let batch = Batch {
+            columns: Vec::new(),
+            rows: Vec::new(),
+            affected_rows: Some(affected),
+            has_more: false,
+        };
+        return Ok((batch, None));
+    }
+
+    let stream = client
+        .query_raw(&statement, params.iter().copied())
+        .await?;
+    futures::pin_mut!(stream);
+    let mut rows = Vec::new();
+    let mut total = 0;
+    while let Some(row) = stream.try_next().await? {
+        total += 1;
+        if rows.len() < max_rows as usize {
+            rows.push(row);
+        }
+    }
+
+    let batch = Batch {
+        columns: column_meta(statement.columns()),
+        rows,
+        affected_rows: None,
+        has_more: false,
+    };
+    Ok((batch, Some(total)))
+}
+
+/// Run a script statement by statement on one session.
+///
+/// Each row-returning statement keeps its first `page_size` rows. With
+/// `in_transaction` the script runs between `BEGIN` and `COMMIT` and is
+/// rolled back at the first error. Otherwise each statement commits on its
+/// own and `stop_on_error` (default true) decides whether an error ends the
+/// script. Statements after a stop are reported as `skipped`. A transaction
+/// the script opens itself and leaves open is rolled back.
+///
+/// Inside a transaction opened with `pg_begin` the script runs in it and
+/// `in_transaction` is ignored. A script that opens a transaction itself
+/// and leaves it open keeps it, as if begun with `pg_begin`; one that ends
+/// the open transaction releases it.
+///
+/// `query_id` works with `pg_cancel_query` to cancel the running statement.
+#[tauri::command]
+pub async fn pg_execute_script(
+    id: String,
+    sql: String,
+    in_transaction: Option<bool>,
+    stop_on_error: Option<bool>,
+    page_size: Option<i64>,
+    query_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<PgStatementResult>, String> {
+    let in_transaction = in_transaction.unwrap_or(false);
+    let stop_on_error = in_transaction || stop_on_error.unwrap_or(true);
+    let max_rows = page_size.unwrap_or(DEFAULT_FETCH_SIZE).max(1);
+
+    let statements = split_statements(&sql);
+    if statements.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let client = checkout(&id, &state).await?;
+    let pinned = client.session().cloned();
+    let in_transaction = in_transaction && pinned.is_none();
+    if in_transaction {
+        client
+            .batch_execute("BEGIN")
+            .await
+            .map_err(|e| format!("Failed to begin transaction: {}", query_error(&e)))?;
+    }
+
+    let query_id = query_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    state.pg_running_queries.lock().await.insert(
+        query_id.clone(),
+        RunningQuery {
+            connection_id: id.clone(),
+            cancel_token: client.cancel_token(),
+        },
+    );
+
+    let mut results = Vec::with_capacity(statements.len());
+    let mut stopped = false;
+    // Whether a transaction block is open on the client
+    let mut open = pinned.is_some();
+    for (index, statement) in statements.iter().enumerate() {
+        let query_type = statement_type(&statement.text).to_string();
+        let mut entry = PgStatementResult {
+            index,
+            sql: statement.text.clone(),
+            line: statement.line,
+            query_type: query_type.clone(),
+            status: "skipped".to_string(),
+            result: None,
+            error: None,
+            duration_ms: 0,
+        };
+        if stopped {
+            results.push(entry);
+            continue;
+        }
+
+        let start = Instant::now();
+        if let Some(ref session) = pinned {
+            session.enter();
+        }
+        let outcome = run_bounded(&client, &statement.text, &[], max_rows).await;
+        if let Some(ref session) = pinned {
+            session.leave(outcome.is_ok());
+        }
+        let duration_ms = start.elapsed().as_millis() as u64;
+        entry.duration_ms = duration_ms;
+
+        if !in_transaction {
+            if ends_transaction(&statement.text) {
+                open = false;
+            } else if begins_transaction(&statement.text) && outcome.is_ok() {
+                open = true;
+            }
+        }
+
+        match outcome {
+            Ok((batch, total_rows)) => {
+                let row_count = total_rows.or(batch.affected_rows.map(|n| n as i64));
+                record_history(&state, &id, &statement.text, duration_ms, row_count, None).await;
+                entry.status = "ok".to_string();
+                entry.result = Some(PgQueryResult {
+                    query_id: query_id.clone(),
+                    columns: batch.columns,
+                    rows: batch.rows.iter().map(row_to_json).collect(),
+                    total_rows,
+                    estimated_rows: None,
+                    has_more: false,
+                    affected_rows: batch.affected_rows,
+                    duration_ms,
+                    query_type,
+                });
+            }
+            Err(e) => {
+                let error_str = query_error(&e);
+                record_history(&state, &id, &statement.text, duration_ms, None, Some(&error_str)).await;
+                entry.status = "error".to_string();
+                entry.error = Some(error_str);
+                stopped = stop_on_error;
+            }
+        }
+        results.push(entry);
+    }
+
+    state.pg_running_queries.lock().await.remove(&query_id);
+    forget_timeout(&state, &id, &client, &sql).await;
+
+    let failed = results.iter().any(|r| r.status == "error");
+    if in_transaction {
+        if failed {
+            let _ = client.batch_execute("ROLLBACK").await;
+        } else {
+            client
+                .batch_execute("COMMIT")
+                .await
+                .map_err(|e| format!("Failed to commit: {}", query_error(&e)))?;
+        }
+        return Ok(results);
+    }
+
+    match client {
+        QueryClient::Session(ref session) if !open => release_session(&state, &id, session).await,
+        QueryClient::Pooled(client) if open => {
+            if let Some(last) = results.last() {
+                let session = PgSession::new(*client);
+                session.failed.store(last.status == "error", Ordering::SeqCst);
+                state.pg_sessions.lock().await.insert(id.clone(), Arc::new(session));
+            }
+        }
+        _ => {}
+    }
+
+    Ok(results)
+}
+
+/// Cancel a query started by `pg_execute_query`. The query then fails with
+/// a cancellation error.
+#[tauri::command]
+pub async fn pg_cancel_query(
+    query_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (connection_id, cancel_token) = {
+        let running = state.pg_running_queries.lock().await;
+        let query = running
+            .get(&query_id)
+            .ok_or_else(|| format!("Query {query_id} is not running"))?;
+        (query.connection_id.clone(), query.cancel_token.clone())
+    };
+
+    let tls = state
+        .pg_tls
+        .lock()
+        .await
+        .get(&connection_id)
+        .cloned()
+        .ok_or("Not connected")?;
+    tls.cancel(&cancel_token).await
+}
+
+// ── Transactions ───────────────────────────────────────────────────────
+
+/// Forget the connection's open transaction if it is still `session`. The
+/// client goes back to the pool once no query holds it.
+async fn release_session(state: &AppState, id: &str, session: &Arc<PgSession>) {
+    let mut sessions = state.pg_sessions.lock().await;
+    if sessions.get(id).is_some_and(|s| Arc::ptr_eq(s, session)) {
+        sessions.remove(id);
+    }
+}
+
+/// Roll back and drop the connection's open transaction, if any. Returns
+/// whether there was one.
+async fn abandon_session(state: &AppState, id: &str) -> bool {
+    let session = state.pg_sessions.lock().await.remove(id);
+    match session {
+        Some(session) => {
+            if let Err(e) = session.client.batch_execute("ROLLBACK").await {
+                log::warn!("Failed to roll back open transaction on {id}: {e}");
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// Open a transaction pinned to one client. Queries on this connection run
+/// in it until `pg_commit` or `pg_rollback`.
+#[tauri::command]
+pub async fn pg_begin(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<PgTransactionStatus, String> {
+    if state.pg_sessions.lock().await.contains_key(&id) {
+        return Err("A transaction is already open on this connection".to_string());
+    }
+
+    let client = checkout_pooled(&id, &state).await?;
+    client
+        .batch_execute("BEGIN")
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", query_error(&e)))?;
+
+    let session = Arc::new(PgSession::new(client));
+    let status = session.status();
+    let mut sessions = state.pg_sessions.lock().await;
+    if sessions.contains_key(&id) {
+        // Lost a race with another BEGIN; this client's transaction is unused
+        let _ = session.client.batch_execute("ROLLBACK").await;
+        return Err("A transaction is already open on this connection".to_string());
+    }
+    sessions.insert(id, session);
+    Ok(status)
+}
+
+/// End the open transaction with `COMMIT` or, when `commit` is false,
+/// `ROLLBACK`, and release its client.
+async fn end_session(id: &str, commit: bool, state: &AppState) -> Result<(), String> {
+    let session = state
+        .pg_sessions
+        .lock()
+        .await
+        .remove(id)
+        .ok_or("No transaction is open on this connection")?;
+    // The server would quietly turn this COMMIT into a ROLLBACK
+    let failed = commit && session.failed.load(Ordering::SeqCst);
+    let sql = if commit && !failed { "COMMIT" } else { "ROLLBACK" };
+    session
+        .client
+        .batch_execute(sql)
+        .await
+        .map_err(|e| format!("{sql} failed: {}", query_error(&e)))?;
+    if failed {
+        return Err("Transaction had a failed statement and was rolled back".to_string());
+    }
+    Ok(())
+}
+
+/// Commit the open transaction and release its client. A transaction that
+/// had a failed statement cannot commit, so it is rolled back and an error
+/// returned.
+#[tauri::command]
+pub async fn pg_commit(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    end_session(&id, true, &state).await
+}
+
+#[tauri::command]
+pub async fn pg_rollback(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    end_session(&id, false, &state).await
+}
+
+/// The open transaction on a connection, or `None`.
+#[tauri::command]
+pub async fn pg_transaction_status(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<PgTransactionStatus>, String> {
+    Ok(state.pg_sessions.lock().await.get(&id).map(|s| s.status()))
+}
+
+// ── Query History ──────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn pg_list_query_history(
+    connection_id: String,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<PgQueryHistoryEntry>, String> {
+    let limit = limit.unwrap_or(50);
+
+    let entries: Vec<PgQueryHistoryEntry> = sqlx::query_as(
+        "SELECT id, connection_id, sql_text, executed_at, duration_ms, row_count, error \
+         FROM pg_query_history \
+         WHERE connection_id = ? \
+         ORDER BY executed_at DESC \
+         LIMIT ?",
+    )
+    .bind(&connection_id)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(entries)
+}
+
+// ── Saved Queries ──────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn pg_save_query(
+    connection_id: Option<String>,
+    label: String,
+    sql_text: String,
+    params: Option<Vec<PgSavedQueryParam>>,
+    state: State<'_, AppState>,
+) -> Result<PgSavedQuery, String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let params_json = match params {
+        Some(params) if !params.is_empty() => {
+            Some(serde_json::to_string(&params).map_err(|e| e.to_string())?)
+        }
+        _ => None,
+    };
+
+    sqlx::query(
+        "INSERT INTO pg_saved_queries (id, connection_id, label, sql_text, params_json) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(&connection_id)
+    .bind(&label)
+    .bind(&sql_text)
+    .bind(&params_json)
+    .execute(&state.db)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let saved: PgSavedQuery = sqlx::query_as(
+        "SELECT id, connection_id, label, sql_text, created_at, params_json \
+         FROM pg_saved_queries WHERE id = ?",
+    )
+    .bind(&id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(saved.decode_params())
+}
+
+#[tauri::command]
 pub async fn pg_list_saved_queries(
     connection_id: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<Vec<PgSavedQuery>, String> {
     let queries: Vec<PgSavedQuery> = if let Some(conn_id) = connection_id {
         sqlx::query_as(
-            "SELECT id, connection_id, label, sql_text, created_at \
+            "SELECT id, connection_id, label, sql_text, created_at, params_json \
              FROM pg_saved_queries \
              WHERE connection_id = ? \
              ORDER BY created_at DESC",
@@ -950,7 +2138,7 @@ pub async fn pg_list_saved_queries(
         .map_err(|e| e.to_string())?
     } else {
         sqlx::query_as(
-            "SELECT id, connection_id, label, sql_text, created_at \
+            "SELECT id, connection_id, label, sql_text, created_at, params_json \
              FROM pg_saved_queries \
              ORDER BY created_at DESC",
         )
@@ -959,7 +2147,7 @@ pub async fn pg_list_saved_queries(
         .map_err(|e| e.to_string())?
     };
 
-    Ok(queries)
+    Ok(queries.into_iter().map(PgSavedQuery::decode_params).collect())
 }
 
 #[tauri::command]
@@ -984,9 +2172,7 @@ pub async fn pg_list_views(
     schema: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<PgViewInfo>, String> {
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+    let client = pooled_client(&id, &state).await?;
 
     let rows = client
         .query(
@@ -1019,9 +2205,7 @@ pub async fn pg_list_functions(
     schema: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<PgFunctionInfo>, String> {
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+    let client = pooled_client(&id, &state).await?;
 
     let rows = client
         .query(
@@ -1060,6 +2244,391 @@ pub async fn pg_list_functions(
     Ok(functions)
 }
 
+// ── Row Editing ────────────────────────────────────────────────────────
+
+type RowValues = serde_json::Map<String, serde_json::Value>;
+
+/// One change to a table's rows from the editable grid. Rows are found by
+/// primary key; `original` holds values as they were read (any subset of
+/// columns) so a row changed since then is reported as a conflict rather
+/// than overwritten.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum PgRowChange {
+    Update {
+        key: RowValues,
+        #[serde(default)]
+        original: RowValues,
+        values: RowValues,
+    },
+    Insert {
+        values: RowValues,
+    },
+    Delete {
+        key: RowValues,
+        #[serde(default)]
+        original: RowValues,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct PgRowConflict {
+    /// Position of the change in the request.
+    pub index: usize,
+    /// `modified` or `deleted`.
+    pub reason: String,
+    /// The row as it is now, if it still exists.
+    pub current: Option<RowValues>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PgRowChangeResult {
+    /// False when there was a conflict, in which case nothing was changed.
+    pub applied: bool,
+    pub affected_rows: u64,
+    /// The conflict that stopped the batch; later changes were not tried.
+    pub conflicts: Vec<PgRowConflict>,
+}
+
+/// A column of the table being edited.
+struct EditColumn {
+    /// Type as `format_type` spells it, typmod included.
+    data_type: String,
+    /// `pg_type.typtype`: `c` for composite types.
+    type_type: i8,
+    is_primary_key: bool,
+}
+
+/// A generated statement and its parameters, built side by side.
+#[derive(Default)]
+struct EditStatement {
+    sql: String,
+    names: Vec<String>,
+    values: Vec<serde_json::Value>,
+}
+
+impl EditStatement {
+    fn param(&mut self, name: &str, value: &serde_json::Value) -> String {
+        self.names.push(name.to_string());
+        self.values.push(value.clone());
+        format!("${}", self.values.len())
+    }
+}
+
+enum ChangeOutcome {
+    Applied(u64),
+    Conflict {
+        reason: &'static str,
+        current: Option<RowValues>,
+    },
+}
+
+async fn load_edit_columns(
+    client: &tokio_postgres::Client,
+    qualified: &str,
+) -> Result<BTreeMap<String, EditColumn>, String> {
+    let rows = client
+        .query(
+            "SELECT a.attname::text, format_type(a.atttypid, a.atttypmod), t.typtype, \
+                    coalesce(a.attnum = ANY(i.indkey), false) \
+             FROM pg_attribute a \
+             JOIN pg_type t ON t.oid = a.atttypid \
+             LEFT JOIN pg_index i ON i.indrelid = a.attrelid AND i.indisprimary \
+             WHERE a.attrelid = $1::text::regclass AND a.attnum > 0 AND NOT a.attisdropped",
+            &[&qualified],
+        )
+        .await
+        .map_err(|e| format!("Failed to read table columns: {}", query_error(&e)))?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let column = EditColumn {
+                data_type: row.get(1),
+                type_type: row.get(2),
+                is_primary_key: row.get(3),
+            };
+            (row.get(0), column)
+        })
+        .collect())
+}
+
+/// Check a change only names columns of the table and finds its row by
+/// exactly the primary key.
+fn validate_change(
+    change: &PgRowChange,
+    columns: &BTreeMap<String, EditColumn>,
+    primary_key: &BTreeSet<&str>,
+) -> Result<(), String> {
+    let (key, values) = match change {
+        PgRowChange::Update { key, values, .. } => (Some(key), Some(values)),
+        PgRowChange::Insert { values } => (None, Some(values)),
+        PgRowChange::Delete { key, .. } => (Some(key), None),
+    };
+
+    if let Some(key) = key {
+        let given: BTreeSet<&str> = key.keys().map(String::as_str).collect();
+        if given != *primary_key {
+            let expected: Vec<&str> = primary_key.iter().copied().collect();
+            return Err(format!(
+                "the key must give the primary key columns ({})",
+                expected.join(", ")
+            ));
+        }
+    }
+    for name in values.into_iter().flat_map(|v| v.keys()) {
+        if !columns.contains_key(name) {
+            return Err(format!("unknown column \"{name}\""));
+        }
+    }
+    Ok(())
+}
+
+/// `WHERE` conditions matching a row by key and by its original values.
+///
+/// Originals are compared through the column type's text form, so types
+/// without an equality operator work and formatting differences in how the
+/// values were read do not count as changes. `json` is compared as `jsonb`
+/// to ignore whitespace. Composite columns and columns the table lacks are
+/// not compared, nor is `money`, which is read assuming two fractional
+/// digits (see `pg_decode::decode`) and may not match under the server's
+/// `lc_monetary`.
+fn row_filter(
+    stmt: &mut EditStatement,
+    columns: &BTreeMap<String, EditColumn>,
+    key: &RowValues,
+    original: &RowValues,
+) -> String {
+    let mut conditions: Vec<String> = key
+        .iter()
+        .map(|(name, value)| format!("{} = {}", quote_ident(name), stmt.param(name, value)))
+        .collect();
+
+    for (name, value) in original {
+        let Some(column) = columns.get(name) else {
+            continue;
+        };
+        if column.is_primary_key || column.type_type == b'c' as i8 || column.data_type == "money" {
+            continue;
+        }
+        let ident = quote_ident(name);
+        let param = stmt.param(name, value);
+        conditions.push(if column.data_type == "json" {
+            format!("{ident}::jsonb IS NOT DISTINCT FROM CAST({param} AS jsonb)")
+        } else {
+            format!(
+                "{ident}::text IS NOT DISTINCT FROM CAST({param} AS {})::text",
+                column.data_type
+            )
+        });
+    }
+    conditions.join(" AND ")
+}
+
+/// Run a generated statement, letting the server infer the parameter types
+/// and converting the values to match.
+async fn query_edit(
+    client: &tokio_postgres::Client,
+    stmt: &EditStatement,
+) -> Result<Vec<Row>, String> {
+    let prepared = client.prepare(&stmt.sql).await.map_err(|e| query_error(&e))?;
+    let (values, casts) = bind_values(&stmt.names, prepared.params(), &stmt.values)?;
+    let sql = cast_placeholders(&stmt.sql, &casts);
+    let params: Vec<&(dyn ToSql + Sync)> = values
+        .iter()
+        .map(|v| v.as_ref() as &(dyn ToSql + Sync))
+        .collect();
+    client
+        .query(sql.as_str(), &params)
+        .await
+        .map_err(|e| query_error(&e))
+}
+
+fn row_to_map(row: &Row) -> RowValues {
+    row.columns()
+        .iter()
+        .map(|c| c.name().to_string())
+        .zip(row_to_json(row))
+        .collect()
+}
+
+async fn apply_change(
+    client: &tokio_postgres::Client,
+    qualified: &str,
+    columns: &BTreeMap<String, EditColumn>,
+    change: &PgRowChange,
+) -> Result<ChangeOutcome, String> {
+    let mut stmt = EditStatement::default();
+    let key = match change {
+        PgRowChange::Insert { values } => {
+            stmt.sql = if values.is_empty() {
+                format!("INSERT INTO {qualified} DEFAULT VALUES RETURNING 1")
+            } else {
+                let names: Vec<String> = values.keys().map(|n| quote_ident(n)).collect();
+                let params: Vec<String> = values.iter().map(|(n, v)| stmt.param(n, v)).collect();
+                format!(
+                    "INSERT INTO {qualified} ({}) VALUES ({}) RETURNING 1",
+                    names.join(", "),
+                    params.join(", ")
+                )
+            };
+            let inserted = query_edit(client, &stmt).await?.len() as u64;
+            return Ok(ChangeOutcome::Applied(inserted));
+        }
+        PgRowChange::Update {
+            key,
+            original,
+            values,
+        } => {
+            if values.is_empty() {
+                return Ok(ChangeOutcome::Applied(0));
+            }
+            let sets: Vec<String> = values
+                .iter()
+                .map(|(n, v)| format!("{} = {}", quote_ident(n), stmt.param(n, v)))
+                .collect();
+            let filter = row_filter(&mut stmt, columns, key, original);
+            stmt.sql = format!(
+                "UPDATE {qualified} SET {} WHERE {filter} RETURNING 1",
+                sets.join(", ")
+            );
+            key
+        }
+        PgRowChange::Delete { key, original } => {
+            let filter = row_filter(&mut stmt, columns, key, original);
+            stmt.sql = format!("DELETE FROM {qualified} WHERE {filter} RETURNING 1");
+            key
+        }
+    };
+
+    let affected = query_edit(client, &stmt).await?.len() as u64;
+    if affected > 0 {
+        return Ok(ChangeOutcome::Applied(affected));
+    }
+
+    // Nothing matched, so the row was changed or deleted since it was read
+    let mut lookup = EditStatement::default();
+    let filter = row_filter(&mut lookup, columns, key, &RowValues::new());
+    lookup.sql = format!("SELECT * FROM {qualified} WHERE {filter}");
+    let current = query_edit(client, &lookup).await?.first().map(row_to_map);
+    Ok(ChangeOutcome::Conflict {
+        reason: if current.is_some() { "modified" } else { "deleted" },
+        current,
+    })
+}
+
+/// Statements that open, commit and roll back a batch of row changes.
+/// Inside an open transaction a savepoint keeps a failed batch from
+/// aborting it.
+fn batch_commands(in_session: bool) -> (&'static str, &'static str, &'static str) {
+    if in_session {
+        (
+            "SAVEPOINT pm_row_changes",
+            "RELEASE SAVEPOINT pm_row_changes",
+            "ROLLBACK TO SAVEPOINT pm_row_changes; RELEASE SAVEPOINT pm_row_changes",
+        )
+    } else {
+        ("BEGIN", "COMMIT", "ROLLBACK")
+    }
+}
+
+/// Apply every change or none. Stops at the first row that is not as it
+/// was read, rolls back and returns that conflict.
+async fn apply_changes(
+    client: &tokio_postgres::Client,
+    qualified: &str,
+    columns: &BTreeMap<String, EditColumn>,
+    changes: &[PgRowChange],
+    in_session: bool,
+) -> Result<PgRowChangeResult, String> {
+    let (begin, commit, rollback) = batch_commands(in_session);
+
+    client
+        .batch_execute(begin)
+        .await
+        .map_err(|e| format!("Failed to begin transaction: {}", query_error(&e)))?;
+
+    let mut affected_rows = 0;
+    for (index, change) in changes.iter().enumerate() {
+        match apply_change(client, qualified, columns, change).await {
+            Ok(ChangeOutcome::Applied(n)) => affected_rows += n,
+            Ok(ChangeOutcome::Conflict { reason, current }) => {
+                let _ = client.batch_execute(rollback).await;
+                return Ok(PgRowChangeResult {
+                    applied: false,
+                    affected_rows: 0,
+                    conflicts: vec![PgRowConflict {
+                        index,
+                        reason: reason.to_string(),
+                        current,
+                    }],
+                });
+            }
+            Err(e) => {
+                let _ = client.batch_execute(rollback).await;
+                return Err(format!("Change {} failed: {e}", index + 1));
+            }
+        }
+    }
+
+    client
+        .batch_execute(commit)
+        .await
+        .map_err(|e| format!("Failed to commit: {}", query_error(&e)))?;
+    Ok(PgRowChangeResult {
+        applied: true,
+        affected_rows,
+        conflicts: Vec::new(),
+    })
+}
+
+/// Apply edits from the grid to `schema.table` in one transaction, as
+/// parameterised `UPDATE`, `INSERT` and `DELETE` statements.
+///
+/// The table must have a primary key. Changes run in order; at the first
+/// row changed or deleted since it was read (see `PgRowChange`), everything
+/// is rolled back and that conflict is returned with `applied` false. Inside
+/// a transaction opened with `pg_begin` the changes join it.
+#[tauri::command]
+pub async fn pg_apply_row_changes(
+    id: String,
+    schema: String,
+    table: String,
+    changes: Vec<PgRowChange>,
+    state: State<'_, AppState>,
+) -> Result<PgRowChangeResult, String> {
+    let client = checkout(&id, &state).await?;
+    let qualified = format!("{}.{}", quote_ident(&schema), quote_ident(&table));
+
+    let columns = load_edit_columns(&client, &qualified).await?;
+    let primary_key: BTreeSet<&str> = columns
+        .iter()
+        .filter(|(_, c)| c.is_primary_key)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if primary_key.is_empty() {
+        return Err(format!(
+            "{schema}.{table} has no primary key, so its rows cannot be edited safely"
+        ));
+    }
+    for (index, change) in changes.iter().enumerate() {
+        validate_change(change, &columns, &primary_key)
+            .map_err(|e| format!("Change {}: {e}", index + 1))?;
+    }
+
+    let session = client.session().cloned();
+    if let Some(ref session) = session {
+        session.enter();
+    }
+    let result = apply_changes(&client, &qualified, &columns, &changes, session.is_some()).await;
+    if let Some(ref session) = session {
+        // A failed batch was rolled back to its savepoint, so the
+        // transaction is still usable
+        session.leave(true);
+    }
+    result
+}
+
 // ── DDL Commands ───────────────────────────────────────────────────────
 
 #[tauri::command]
@@ -1102,9 +2671,7 @@ pub async fn pg_create_table(
         col_defs.join(",\n  ")
     );
 
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+    let client = pooled_client(&id, &state).await?;
 
     client
         .execute(sql.as_str(), &[])
@@ -1137,9 +2704,7 @@ pub async fn pg_add_column(
         sql.push_str(&format!(" DEFAULT {}", default));
     }
 
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+    let client = pooled_client(&id, &state).await?;
 
     client
         .execute(sql.as_str(), &[])
@@ -1175,9 +2740,7 @@ pub async fn pg_drop_object(
         sql.push_str(" CASCADE");
     }
 
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+    let client = pooled_client(&id, &state).await?;
 
     client
         .execute(sql.as_str(), &[])
@@ -1203,9 +2766,7 @@ pub async fn pg_rename_table(
         schema, old_name, new_name
     );
 
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
+    let client = pooled_client(&id, &state).await?;
 
     client
         .execute(sql.as_str(), &[])
@@ -1227,12 +2788,7 @@ pub async fn pg_export_csv(
     file_path: String,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
-
-    // Drop the lock before executing the query
-    drop(pools);
+    let client = checkout(&id, &state).await?;
 
     let rows = client
         .query(sql.as_str(), &[])
@@ -1286,12 +2842,7 @@ pub async fn pg_export_json(
     file_path: String,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    let pools = state.pg_pools.lock().await;
-    let pool = pools.get(&id).ok_or("Not connected")?;
-    let client = pool.get().await.map_err(|e| format!("Pool error: {e}"))?;
-
-    // Drop the lock before executing the query
-    drop(pools);
+    let client = checkout(&id, &state).await?;
 
     let rows = client
         .query(sql.as_str(), &[])
@@ -1322,3 +2873,200 @@ pub async fn pg_export_json(
         file_path
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn row(value: serde_json::Value) -> RowValues {
+        value.as_object().cloned().unwrap()
+    }
+
+    fn column(data_type: &str, type_type: u8, is_primary_key: bool) -> EditColumn {
+        EditColumn {
+            data_type: data_type.to_string(),
+            type_type: type_type as i8,
+            is_primary_key,
+        }
+    }
+
+    #[test]
+    fn filters_rows_by_key_and_original_text() {
+        let columns = BTreeMap::from([
+            ("addr".to_string(), column("address", b'c', false)),
+            ("doc".to_string(), column("json", b'b', false)),
+            ("id".to_string(), column("integer", b'b', true)),
+            ("price".to_string(), column("money", b'b', false)),
+            (
+                "name".to_string(),
+                column("character varying(20)", b'b', false),
+            ),
+        ]);
+        let key = row(json!({"id": 1}));
+        let original = row(json!({
+            "addr": "(1,x)",
+            "doc": {"a": 1},
+            "id": 1,
+            "missing": 2,
+            "name": "a",
+            "price": "1234.56",
+        }));
+
+        let mut stmt = EditStatement::default();
+        let filter = row_filter(&mut stmt, &columns, &key, &original);
+        assert_eq!(
+            filter,
+            "\"id\" = $1 \
+             AND \"doc\"::jsonb IS NOT DISTINCT FROM CAST($2 AS jsonb) \
+             AND \"name\"::text IS NOT DISTINCT FROM CAST($3 AS character varying(20))::text"
+        );
+        assert_eq!(stmt.names, ["id", "doc", "name"]);
+        assert_eq!(stmt.values, [json!(1), json!({"a": 1}), json!("a")]);
+    }
+
+    #[test]
+    fn uses_a_savepoint_inside_a_session() {
+        assert_eq!(batch_commands(false), ("BEGIN", "COMMIT", "ROLLBACK"));
+        let (begin, commit, rollback) = batch_commands(true);
+        assert_eq!(begin, "SAVEPOINT pm_row_changes");
+        assert_eq!(commit, "RELEASE SAVEPOINT pm_row_changes");
+        assert!(rollback.starts_with("ROLLBACK TO SAVEPOINT pm_row_changes;"));
+    }
+
+    /// A connection to the server in `PG_TEST_URL`, with a `pm_edit` temp
+    /// table. `None` (and the test passes trivially) when it is not set.
+    async fn edit_table() -> Option<(tokio_postgres::Client, BTreeMap<String, EditColumn>)> {
+        let url = std::env::var("PG_TEST_URL").ok()?;
+        let (client, connection) = tokio_postgres::connect(&url, tokio_postgres::NoTls)
+            .await
+            .expect("connect to PG_TEST_URL");
+        tokio::spawn(connection);
+        client
+            .batch_execute(
+                "CREATE TEMP TABLE pm_edit (id int PRIMARY KEY, name text, doc json);
+                 INSERT INTO pm_edit VALUES (1, 'a', '{\"x\":  1}'), (2, 'b', NULL);",
+            )
+            .await
+            .unwrap();
+        let columns = load_edit_columns(&client, "pg_temp.pm_edit").await.unwrap();
+        Some((client, columns))
+    }
+
+    async fn names(client: &tokio_postgres::Client) -> Vec<(i32, String)> {
+        client
+            .query("SELECT id, name FROM pm_edit ORDER BY id", &[])
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| (r.get(0), r.get(1)))
+            .collect()
+    }
+
+    fn changes(value: serde_json::Value) -> Vec<PgRowChange> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_first_conflict() {
+        let Some((client, columns)) = edit_table().await else {
+            return;
+        };
+        let batch = changes(json!([
+            // json is compared as jsonb, so whitespace is not a change
+            {"kind": "update", "key": {"id": 1}, "original": {"name": "a", "doc": {"x": 1}},
+             "values": {"name": "z"}},
+            {"kind": "delete", "key": {"id": 2}, "original": {"name": "stale"}},
+            {"kind": "delete", "key": {"id": 9}},
+        ]));
+
+        let result = apply_changes(&client, "pg_temp.pm_edit", &columns, &batch, false)
+            .await
+            .unwrap();
+        assert!(!result.applied);
+        assert_eq!(result.affected_rows, 0);
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!((conflict.index, conflict.reason.as_str()), (1, "modified"));
+        assert_eq!(conflict.current.as_ref().unwrap()["name"], json!("b"));
+        assert_eq!(names(&client).await, [(1, "a".into()), (2, "b".into())]);
+
+        let batch = changes(json!([{"kind": "delete", "key": {"id": 9}}]));
+        let result = apply_changes(&client, "pg_temp.pm_edit", &columns, &batch, false)
+            .await
+            .unwrap();
+        assert_eq!(result.conflicts[0].reason, "deleted");
+        assert!(result.conflicts[0].current.is_none());
+    }
+
+    #[tokio::test]
+    async fn keeps_an_open_transaction_usable() {
+        let Some((client, columns)) = edit_table().await else {
+            return;
+        };
+        client.batch_execute("BEGIN").await.unwrap();
+
+        let conflicting = changes(json!([
+            {"kind": "update", "key": {"id": 1}, "values": {"name": "x"}},
+            {"kind": "delete", "key": {"id": 2}, "original": {"name": "stale"}},
+        ]));
+        let result = apply_changes(&client, "pg_temp.pm_edit", &columns, &conflicting, true)
+            .await
+            .unwrap();
+        assert!(!result.applied);
+
+        let failing = changes(json!([{"kind": "insert", "values": {"id": 1}}]));
+        let err = apply_changes(&client, "pg_temp.pm_edit", &columns, &failing, true)
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("Change 1 failed"), "{err}");
+
+        let batch = changes(json!([
+            {"kind": "update", "key": {"id": 1}, "original": {"name": "a"}, "values": {"name": "y"}},
+            {"kind": "insert", "values": {"id": 3, "name": "c"}},
+        ]));
+        let result = apply_changes(&client, "pg_temp.pm_edit", &columns, &batch, true)
+            .await
+            .unwrap();
+        assert!(result.applied);
+        assert_eq!(result.affected_rows, 2);
+        assert_eq!(
+            names(&client).await,
+            [(1, "y".into()), (2, "b".into()), (3, "c".into())]
+        );
+
+        // The outer transaction still decides
+        client.batch_execute("ROLLBACK").await.unwrap();
+        assert_eq!(names(&client).await, [(1, "a".into()), (2, "b".into())]);
+    }
+
+    #[tokio::test]
+    async fn runs_directly_what_a_cursor_cannot_hold() {
+        let Some((client, _)) = edit_table().await else {
+            return;
+        };
+
+        let batch = open_cursor(&client, "SELECT 1 AS n INTO TEMP pm_into", &[], 100)
+            .await
+            .unwrap();
+        assert!(batch.columns.is_empty());
+        assert_eq!(batch.affected_rows, Some(1));
+
+        let sql = "WITH d AS (DELETE FROM pm_edit WHERE id = 2 RETURNING id) SELECT id FROM d";
+        let batch = open_cursor(&client, sql, &[], 100).await.unwrap();
+        assert_eq!(batch.rows.len(), 1);
+        assert!(!batch.has_more);
+        assert_eq!(names(&client).await, [(1, "a".into())]);
+
+        // Errors come from the original SQL, not the DECLARE around it
+        let Err(e) = open_cursor(&client, "SELECT FROM pm_nope", &[], 100).await else {
+            panic!("query on a missing table succeeded");
+        };
+        assert_eq!(e.code(), Some(&SqlState::UNDEFINED_TABLE));
+        let position = e.as_db_error().and_then(|e| e.position()).cloned();
+        assert!(matches!(
+            position,
+            Some(tokio_postgres::error::ErrorPosition::Original(13))
+        ));
+    }
+}
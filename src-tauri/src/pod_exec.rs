@@ -0,0 +1,272 @@
+use futures::channel::mpsc::Sender;
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{AttachParams, TerminalSize};
+use kube::Api;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::k8s::build_client;
+use crate::AppState;
+
+/// Name of the Tauri event carrying terminal output.
+pub const POD_EXEC_EVENT: &str = "pod-exec";
+
+/// Shell started when the caller does not pass a command: bash when the
+/// image has it, plain sh otherwise.
+const DEFAULT_SHELL: &[&str] = &[
+    "/bin/sh",
+    "-c",
+    "command -v bash >/dev/null 2>&1 && exec bash || exec sh",
+];
+
+// ---------------------------------------------------------------------------
+// Models
+// ---------------------------------------------------------------------------
+
+/// Handles kept for a live exec session.
+pub struct ExecSession {
+    stdin: UnboundedSender<Vec<u8>>,
+    resize: Option<Sender<TerminalSize>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PodExecOutput {
+    Stdout { data: String },
+    Stderr { data: String },
+    /// The remote process exited; the session is gone.
+    Exit { success: bool, message: Option<String> },
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PodExecEvent {
+    pub session_id: String,
+    #[serde(flatten)]
+    pub output: PodExecOutput,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Drain the valid UTF-8 prefix of `buf`, keeping an incomplete trailing
+/// multi-byte sequence for the next read. Invalid bytes are replaced.
+fn take_utf8(buf: &mut Vec<u8>) -> String {
+    match std::str::from_utf8(buf) {
+        Ok(s) => {
+            let out = s.to_string();
+            buf.clear();
+            out
+        }
+        Err(e) if e.error_len().is_none() => {
+            let rest = buf.split_off(e.valid_up_to());
+            let out = String::from_utf8_lossy(buf).into_owned();
+            *buf = rest;
+            out
+        }
+        Err(_) => {
+            let out = String::from_utf8_lossy(buf).into_owned();
+            buf.clear();
+            out
+        }
+    }
+}
+
+/// Pump one output stream of the remote process into `pod-exec` events.
+async fn forward_output<R, F>(mut reader: R, emit: F, to_output: fn(String) -> PodExecOutput)
+where
+    R: AsyncRead + Unpin,
+    F: Fn(PodExecOutput),
+{
+    let mut chunk = [0u8; 8192];
+    let mut pending: Vec<u8> = Vec::new();
+    loop {
+        match reader.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                pending.extend_from_slice(&chunk[..n]);
+                let data = take_utf8(&mut pending);
+                if !data.is_empty() {
+                    emit(to_output(data));
+                }
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Open an interactive exec session in a pod container.
+///
+/// With `tty` (the default) stdout and stderr are merged by the kubelet, as in
+/// `kubectl exec -it`. Output arrives as `pod-exec` events; input goes through
+/// `pod_exec_write`. Returns the session ID.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn pod_exec_open(
+    kubeconfig_id: String,
+    namespace: String,
+    pod: String,
+    container: Option<String>,
+    command: Option<Vec<String>>,
+    tty: Option<bool>,
+    cols: Option<u16>,
+    rows: Option<u16>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let tty = tty.unwrap_or(true);
+    let command: Vec<String> = command
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| DEFAULT_SHELL.iter().map(|s| s.to_string()).collect());
+
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    let pods: Api<Pod> = Api::namespaced(client, &namespace);
+
+    let mut params = AttachParams::default()
+        .stdin(true)
+        .stdout(true)
+        .stderr(!tty)
+        .tty(tty);
+    if let Some(c) = container {
+        params = params.container(c);
+    }
+
+    let mut attached = pods
+        .exec(&pod, command, &params)
+        .await
+        .map_err(|e| format!("Failed to exec into pod/{pod}: {e}"))?;
+
+    let mut remote_stdin = attached.stdin().ok_or("Exec session has no stdin")?;
+    let stdout = attached.stdout().ok_or("Exec session has no stdout")?;
+    let stderr = attached.stderr();
+    let status = attached.take_status();
+    let mut resize = attached.terminal_size();
+
+    if let (Some(sender), Some(width), Some(height)) = (resize.as_mut(), cols, rows) {
+        let _ = sender.try_send(TerminalSize { width, height });
+    }
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let (stdin_tx, mut stdin_rx) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+
+    tokio::spawn(async move {
+        while let Some(data) = stdin_rx.recv().await {
+            if remote_stdin.write_all(&data).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Keep the map locked until the session is in, so a command that exits
+    // at once cannot remove its entry first and leave a stale session behind.
+    let mut sessions = state.exec_sessions.lock().await;
+    let registry = state.exec_sessions.clone();
+    let task_session_id = session_id.clone();
+    let task = tokio::spawn(async move {
+        let emit = |output: PodExecOutput| {
+            let event = PodExecEvent {
+                session_id: task_session_id.clone(),
+                output,
+            };
+            if let Err(e) = app.emit(POD_EXEC_EVENT, event) {
+                log::warn!("Failed to emit pod exec event: {e}");
+            }
+        };
+
+        let stdout_done = forward_output(stdout, emit, |data| PodExecOutput::Stdout { data });
+        match stderr {
+            Some(stderr) => {
+                let stderr_done =
+                    forward_output(stderr, emit, |data| PodExecOutput::Stderr { data });
+                tokio::join!(stdout_done, stderr_done);
+            }
+            None => stdout_done.await,
+        }
+
+        let exit_status = match status {
+            Some(status) => status.await,
+            None => None,
+        };
+        let success = exit_status
+            .as_ref()
+            .and_then(|s| s.status.as_deref())
+            .map(|s| s == "Success")
+            .unwrap_or(true);
+        emit(PodExecOutput::Exit {
+            success,
+            message: exit_status.and_then(|s| s.message),
+        });
+
+        if let Err(e) = attached.join().await {
+            log::warn!("pod exec session ended with error: {e}");
+        }
+        registry.lock().await.remove(&task_session_id);
+    });
+
+    sessions.insert(
+        session_id.clone(),
+        ExecSession {
+            stdin: stdin_tx,
+            resize,
+            task,
+        },
+    );
+
+    Ok(session_id)
+}
+
+/// Send terminal input to an exec session.
+#[tauri::command]
+pub async fn pod_exec_write(
+    session_id: String,
+    data: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let sessions = state.exec_sessions.lock().await;
+    let session = sessions
+        .get(&session_id)
+        .ok_or_else(|| format!("No exec session for id {session_id}"))?;
+    session
+        .stdin
+        .send(data.into_bytes())
+        .map_err(|_| "Exec session is closed".to_string())
+}
+
+/// Propagate a terminal resize to an exec session.
+#[tauri::command]
+pub async fn pod_exec_resize(
+    session_id: String,
+    cols: u16,
+    rows: u16,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let mut sessions = state.exec_sessions.lock().await;
+    let session = sessions
+        .get_mut(&session_id)
+        .ok_or_else(|| format!("No exec session for id {session_id}"))?;
+    if let Some(resize) = session.resize.as_mut() {
+        resize
+            .try_send(TerminalSize {
+                width: cols,
+                height: rows,
+            })
+            .map_err(|e| format!("Failed to resize terminal: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Close an exec session, killing the connection to the remote process.
+#[tauri::command]
+pub async fn pod_exec_close(session_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let mut sessions = state.exec_sessions.lock().await;
+    if let Some(session) = sessions.remove(&session_id) {
+        session.task.abort();
+    }
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use futures::{AsyncBufReadExt, StreamExt};
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::LogParams;
+use kube::Api;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::k8s::build_client;
+use crate::AppState;
+
+/// Name of the Tauri event carrying log lines.
+pub const POD_LOG_EVENT: &str = "pod-log";
+
+// ---------------------------------------------------------------------------
+// Models
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PodLogChunk {
+    Line { line: String },
+    /// The log stream ended (container exited, or `follow` was off).
+    End,
+    Error { message: String },
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PodLogEvent {
+    pub stream_id: String,
+    #[serde(flatten)]
+    pub chunk: PodLogChunk,
+}
+
+// ---------------------------------------------------------------------------
+// Commands
+// ---------------------------------------------------------------------------
+
+/// Stream the logs of a pod container as `pod-log` events.
+///
+/// * `container` -- required for multi-container pods
+/// * `since` -- only return logs newer than this many seconds
+/// * `tail_lines` -- start with at most this many lines of history
+/// * `previous` -- read the logs of the previous (crashed) container instance
+///
+/// Returns the stream ID to pass to `stop_pod_logs`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn stream_pod_logs(
+    kubeconfig_id: String,
+    namespace: String,
+    pod: String,
+    container: Option<String>,
+    since: Option<i64>,
+    tail_lines: Option<i64>,
+    follow: bool,
+    previous: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let client = build_client(&kubeconfig_id, &state.db).await?;
+    let pods: Api<Pod> = Api::namespaced(client, &namespace);
+
+    let params = LogParams {
+        container,
+        follow,
+        previous: previous.unwrap_or(false),
+        since_seconds: since,
+        tail_lines,
+        ..LogParams::default()
+    };
+
+    // Open the stream before returning so bad pod/container names surface as
+    // command errors instead of a single error event.
+    let reader = pods
+        .log_stream(&pod, &params)
+        .await
+        .map_err(|e| format!("Failed to open logs for pod/{pod}: {e}"))?;
+
+    let stream_id = uuid::Uuid::new_v4().to_string();
+    let event_stream_id = stream_id.clone();
+
+    let read_logs = async move {
+        let emit = |chunk: PodLogChunk| {
+            let event = PodLogEvent {
+                stream_id: event_stream_id.clone(),
+                chunk,
+            };
+            if let Err(e) = app.emit(POD_LOG_EVENT, event) {
+                log::warn!("Failed to emit pod log event: {e}");
+            }
+        };
+
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next().await {
+            match line {
+                Ok(line) => emit(PodLogChunk::Line { line }),
+                Err(e) => {
+                    emit(PodLogChunk::Error {
+                        message: e.to_string(),
+                    });
+                    return;
+                }
+            }
+        }
+        emit(PodLogChunk::End);
+    };
+
+    // Keep the map locked until the entry is in, so a stream that ends at
+    // once cannot remove itself first and leave a stale handle behind.
+    let mut streams = state.log_streams.lock().await;
+    let registry = state.log_streams.clone();
+    let id = stream_id.clone();
+    let handle = tokio::spawn(async move {
+        read_logs.await;
+        registry.lock().await.remove(&id);
+    });
+    streams.insert(stream_id.clone(), handle);
+
+    Ok(stream_id)
+}
+
+/// Stop a log stream started by `stream_pod_logs`.
+#[tauri::command]
+pub async fn stop_pod_logs(stream_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let mut streams = state.log_streams.lock().await;
+    if let Some(handle) = streams.remove(&stream_id) {
+        handle.abort();
+    }
+    Ok(())
+}
@@ -8,6 +8,7 @@ export interface ActiveForward {
   namespace: string
   resource_type: string
   resource_name: string
+  target_pod: string | null
   remote_port: number
   local_port: number
   pid: number | null
@@ -8,7 +8,14 @@ import type {
   PgIndexInfo,
   PgQueryHistoryEntry,
   PgSavedQuery,
+  PgSavedQueryParam,
+  PgQueryDescription,
   QueryTab,
+  SqlStatement,
+  PgStatementResult,
+  PgTransactionStatus,
+  PgRowChange,
+  PgRowChangeResult,
   PgViewInfo,
   PgFunctionInfo,
 } from '@/types/pgmanager'
@@ -24,6 +31,10 @@ export interface SaveConnectionParams {
   username: string
   password?: string
   sslMode: string
+  sslCaSecretId?: string
+  sslCertSecretId?: string
+  sslKeySecretId?: string
+  statementTimeoutMs?: number
   color?: string
 }
 
@@ -33,6 +44,10 @@ export interface TestConnectionParams {
   databaseName: string
   username: string
   password: string
+  sslMode?: string
+  sslCaSecretId?: string
+  sslCertSecretId?: string
+  sslKeySecretId?: string
 }
 
 // ── Module-level singleton state ──────────────────────────────────
@@ -47,6 +62,9 @@ const indexes = ref<PgIndexInfo[]>([])
 
 const queryResult = ref<PgQueryResult | null>(null)
 const queryLoading = ref(false)
+const runningQueryId = ref<string | null>(null)
+// open transaction on the active connection, if any
+const transaction = ref<PgTransactionStatus | null>(null)
 const queryError = ref<string | null>(null)
 
 const queryHistory = ref<PgQueryHistoryEntry[]>([])
@@ -58,6 +76,9 @@ const tabs = ref<QueryTab[]>([{
   sql: '',
   cursorPos: 0,
   result: null,
+  scriptResults: null,
+  params: [],
+  paramValues: {},
   error: null,
   loading: false,
 }])
@@ -95,6 +116,10 @@ export function usePgManager() {
       username: params.username,
       password: params.password,
       sslMode: params.sslMode,
+      sslCaSecretId: params.sslCaSecretId,
+      sslCertSecretId: params.sslCertSecretId,
+      sslKeySecretId: params.sslKeySecretId,
+      statementTimeoutMs: params.statementTimeoutMs,
       color: params.color,
     })
     await loadConnections()
@@ -122,6 +147,10 @@ export function usePgManager() {
       databaseName: params.databaseName,
       username: params.username,
       password: params.password,
+      sslMode: params.sslMode,
+      sslCaSecretId: params.sslCaSecretId,
+      sslCertSecretId: params.sslCertSecretId,
+      sslKeySecretId: params.sslKeySecretId,
     })
   }
 
@@ -136,6 +165,7 @@ export function usePgManager() {
     await invoke('pg_disconnect', { id })
     connectedIds.value = new Set([...connectedIds.value].filter((x) => x !== id))
     if (activeConnectionId.value === id) {
+      transaction.value = null
       schemas.value = []
       tables.value = []
       columns.value = []
@@ -190,22 +220,137 @@ export function usePgManager() {
 
   // ── Query methods ───────────────────────────────────────────────────
 
-  async function executeQuery(sql: string, page = 0, pageSize = 100) {
+  // params bind $1.. / :name placeholders in the order pg_describe_query reports
+  async function executeQuery(sql: string, pageSize = 100, estimate = false, params?: (string | null)[]) {
     if (!activeConnectionId.value) return
     queryLoading.value = true
     queryError.value = null
+    const queryId = crypto.randomUUID()
+    runningQueryId.value = queryId
     try {
       queryResult.value = await invoke<PgQueryResult>('pg_execute_query', {
         id: activeConnectionId.value,
         sql,
-        page,
+        params,
         pageSize,
+        estimate,
+        queryId,
       })
     } catch (e) {
       queryError.value = String(e)
       queryResult.value = null
     } finally {
       queryLoading.value = false
+      if (runningQueryId.value === queryId) runningQueryId.value = null
+      await loadTransactionStatus()
+    }
+  }
+
+  async function describeQuery(sql: string) {
+    if (!activeConnectionId.value) return null
+    return invoke<PgQueryDescription>('pg_describe_query', {
+      id: activeConnectionId.value,
+      sql,
+    })
+  }
+
+  // Apply grid edits to a table in one transaction
+  async function applyRowChanges(schema: string, table: string, changes: PgRowChange[]) {
+    if (!activeConnectionId.value) throw new Error('Not connected')
+    return invoke<PgRowChangeResult>('pg_apply_row_changes', {
+      id: activeConnectionId.value,
+      schema,
+      table,
+      changes,
+    })
+  }
+
+  async function splitSql(sql: string) {
+    return invoke<SqlStatement[]>('pg_split_sql', { sql })
+  }
+
+  // Run a multi-statement script; errors are reported per statement
+  async function executeScript(sql: string, inTransaction = false, pageSize = 100) {
+    if (!activeConnectionId.value) return []
+    const queryId = crypto.randomUUID()
+    runningQueryId.value = queryId
+    try {
+      return await invoke<PgStatementResult[]>('pg_execute_script', {
+        id: activeConnectionId.value,
+        sql,
+        inTransaction,
+        pageSize,
+        queryId,
+      })
+    } finally {
+      if (runningQueryId.value === queryId) runningQueryId.value = null
+      await loadTransactionStatus()
+    }
+  }
+
+  async function cancelQuery() {
+    if (!runningQueryId.value) return
+    await invoke('pg_cancel_query', { queryId: runningQueryId.value })
+  }
+
+  // Append the next rows of a paged result to it in place
+  async function fetchMore(result: PgQueryResult, count = 100) {
+    if (!result.has_more) return
+    runningQueryId.value = result.query_id
+    try {
+      const more = await invoke<PgQueryResult>('pg_fetch_more', {
+        queryId: result.query_id,
+        count,
+      })
+      result.rows.push(...more.rows)
+      result.has_more = more.has_more
+      result.total_rows = more.total_rows
+    } finally {
+      if (runningQueryId.value === result.query_id) runningQueryId.value = null
+    }
+  }
+
+  // Release the server-side cursor of a result that still has rows
+  async function closeQuery(result: PgQueryResult | null) {
+    if (!result?.has_more) return
+    result.has_more = false
+    await invoke('pg_close_query', { queryId: result.query_id })
+  }
+
+  // ── Transaction methods ─────────────────────────────────────────────
+
+  async function loadTransactionStatus() {
+    if (!activeConnectionId.value) {
+      transaction.value = null
+      return
+    }
+    transaction.value = await invoke<PgTransactionStatus | null>('pg_transaction_status', {
+      id: activeConnectionId.value,
+    })
+  }
+
+  async function beginTransaction() {
+    if (!activeConnectionId.value) return
+    transaction.value = await invoke<PgTransactionStatus>('pg_begin', {
+      id: activeConnectionId.value,
+    })
+  }
+
+  async function commitTransaction() {
+    if (!activeConnectionId.value) return
+    try {
+      await invoke('pg_commit', { id: activeConnectionId.value })
+    } finally {
+      await loadTransactionStatus()
+    }
+  }
+
+  async function rollbackTransaction() {
+    if (!activeConnectionId.value) return
+    try {
+      await invoke('pg_rollback', { id: activeConnectionId.value })
+    } finally {
+      await loadTransactionStatus()
     }
   }
 
@@ -218,11 +363,12 @@ export function usePgManager() {
     })
   }
 
-  async function saveQuery(label: string, sqlText: string) {
+  async function saveQuery(label: string, sqlText: string, params: PgSavedQueryParam[] = []) {
     await invoke('pg_save_query', {
       connectionId: activeConnectionId.value,
       label,
       sqlText,
+      params,
     })
     await loadSavedQueries()
   }
@@ -248,6 +394,9 @@ export function usePgManager() {
       sql: sql ?? '',
       cursorPos: 0,
       result: null,
+      scriptResults: null,
+      params: [],
+      paramValues: {},
       error: null,
       loading: false,
     }
@@ -258,6 +407,7 @@ export function usePgManager() {
   function closeTab(tabId: string) {
     const idx = tabs.value.findIndex(t => t.id === tabId)
     if (idx === -1 || tabs.value.length <= 1) return
+    closeQuery(tabs.value[idx]!.result).catch(() => {})
     tabs.value.splice(idx, 1)
     if (activeTabId.value === tabId) {
       activeTabId.value = tabs.value[Math.min(idx, tabs.value.length - 1)]!.id
@@ -371,6 +521,8 @@ export function usePgManager() {
     indexes,
     queryResult,
     queryLoading,
+    runningQueryId,
+    transaction,
     queryError,
     queryHistory,
     savedQueries,
@@ -392,6 +544,17 @@ export function usePgManager() {
     loadIndexes,
     getRowCount,
     executeQuery,
+    cancelQuery,
+    describeQuery,
+    applyRowChanges,
+    splitSql,
+    executeScript,
+    fetchMore,
+    closeQuery,
+    loadTransactionStatus,
+    beginTransaction,
+    commitTransaction,
+    rollbackTransaction,
     loadHistory,
     saveQuery,
     loadSavedQueries,
@@ -5,6 +5,16 @@ export interface KubeconfigInfo {
   last_used: string | null
 }
 
+export interface ClusterStatus {
+  kubeconfig_id: string
+  reachable: boolean
+  auth_valid: boolean
+  server_version: string | null
+  latency_ms: number | null
+  error_msg: string | null
+  checked_at: string
+}
+
 export interface K8sService {
   name: string
   namespace: string
@@ -23,6 +33,29 @@ export interface K8sPod {
   namespace: string
   status: string
   ports: PodPort[]
+  ready: boolean
+  restart_count: number
+  containers: PodContainer[]
+  node_name: string | null
+  pod_ip: string | null
+  owner: PodOwner | null
+  created_at: string | null
+}
+
+export interface PodContainer {
+  name: string
+  image: string | null
+  ready: boolean
+  restart_count: number
+  state: 'running' | 'waiting' | 'terminated' | 'unknown'
+  state_reason: string | null
+  last_termination_reason: string | null
+  last_exit_code: number | null
+}
+
+export interface PodOwner {
+  kind: string
+  name: string
 }
 
 export interface PodPort {
@@ -31,7 +64,21 @@ export interface PodPort {
   protocol: string
 }
 
+export type DbEngine = 'postgres' | 'mysql' | 'redis' | 'mongodb' | 'amqp' | 'kafka'
+
+export interface SuggestedPort {
+  service_name: string
+  port: number
+  port_name: string | null
+}
+
+export interface FieldSource {
+  object: string
+  key: string
+}
+
 export interface DetectedCredentials {
+  engine: DbEngine
   source: string
   host: string | null
   port: number | null
@@ -40,4 +87,163 @@ export interface DetectedCredentials {
   password: string | null
   ssl_mode: string | null
   confidence: number
+  field_sources: Partial<Record<'host' | 'port' | 'database' | 'username' | 'password' | 'ssl_mode', FieldSource>>
+  suggested_ports: SuggestedPort[]
+}
+
+export type WatchChange<T> =
+  | { type: 'synced'; items: T[] }
+  | { type: 'added'; item: T }
+  | { type: 'modified'; item: T }
+  | { type: 'deleted'; item: T }
+  | { type: 'error'; message: string }
+
+export type WatchEvent<T> = WatchChange<T> & {
+  watch_id: string
+  resource: 'pod' | 'service'
+}
+
+export type PodLogEvent = { stream_id: string } & (
+  | { type: 'line'; line: string }
+  | { type: 'end' }
+  | { type: 'error'; message: string }
+)
+
+export type PodExecEvent = { session_id: string } & (
+  | { type: 'stdout'; data: string }
+  | { type: 'stderr'; data: string }
+  | { type: 'exit'; success: boolean; message: string | null }
+)
+
+export interface SearchHit {
+  kubeconfig_id: string
+  kubeconfig_name: string
+  namespace: string
+  kind: 'service' | 'pod'
+  name: string
+  matched_on: string
+  labels: Record<string, string>
+}
+
+export interface SearchClusterError {
+  kubeconfig_id: string
+  kubeconfig_name: string
+  error: string
+}
+
+export interface SearchResults {
+  hits: SearchHit[]
+  errors: SearchClusterError[]
+}
+
+export interface K8sEvent {
+  event_type: string
+  reason: string | null
+  message: string | null
+  involved_kind: string | null
+  involved_name: string | null
+  source: string | null
+  count: number | null
+  first_seen: string | null
+  last_seen: string | null
+}
+
+export interface ConfirmationToken {
+  token: string
+  action: 'delete_pod' | 'rollout_restart' | 'scale_down'
+  target: string
+  expires_in_secs: number
+}
+
+export interface AuditEntry {
+  id: string
+  kubeconfig_id: string
+  kubeconfig_name: string | null
+  namespace: string
+  action: string
+  target: string
+  detail: string | null
+  outcome: 'success' | 'error'
+  error_msg: string | null
+  created_at: string
+}
+
+export interface K8sConfigMap {
+  name: string
+  namespace: string
+  // data and binaryData entries, masked
+  entries: K8sConfigEntry[]
+  created_at: string | null
+}
+
+export interface K8sSecretSummary {
+  name: string
+  namespace: string
+  secret_type: string
+  keys: string[]
+  created_at: string | null
+}
+
+// A masked ConfigMap or Secret entry; value is always null until revealed
+export interface K8sConfigEntry {
+  key: string
+  size: number
+  binary: boolean
+  value: string | null
+}
+
+export interface K8sSecret {
+  name: string
+  namespace: string
+  secret_type: string
+  entries: K8sConfigEntry[]
+  created_at: string | null
+}
+
+export interface RevealedValue {
+  key: string
+  value: string
+  binary: boolean
+}
+
+export interface IngressRoute {
+  host: string | null
+  path: string
+  path_type: string
+  service_name: string | null
+  service_port: string | null
+  tls: boolean
+  url: string | null
+}
+
+export interface K8sIngress {
+  name: string
+  namespace: string
+  class_name: string | null
+  routes: IngressRoute[]
+}
+
+export interface EndpointAddress {
+  ip: string
+  pod_name: string | null
+  node_name: string | null
+}
+
+export interface EndpointPort {
+  name: string | null
+  port: number
+  protocol: string
+}
+
+export interface K8sServiceEndpoints {
+  service_name: string
+  namespace: string
+  ready: EndpointAddress[]
+  not_ready: EndpointAddress[]
+  ports: EndpointPort[]
+}
+
+export interface AccessReview {
+  allowed: boolean
+  reason: string | null
 }
@@ -1,3 +1,5 @@
+import type { ActiveForward } from '@/composables/useForwards'
+
 export interface PgConnection {
   id: string
   label: string | null
@@ -9,25 +11,54 @@ export interface PgConnection {
   username: string
   // password is never sent to frontend (skip_serializing in Rust)
   ssl_mode: string
+  // vault secret IDs for the CA, client certificate and client key
+  ssl_ca_secret_id: string | null
+  ssl_cert_secret_id: string | null
+  ssl_key_secret_id: string | null
+  statement_timeout_ms: number | null
   color: string | null
   created_at: string
   last_used: string | null
 }
 
+export interface ProvisionedDbAccess {
+  connection: PgConnection
+  forward: ActiveForward
+  favorite_id: string
+  message: string
+}
+
 export interface PgColumnMeta {
   name: string
+  // SQL spelling, e.g. numeric, int4[] or public.mood
   data_type: string
+  type_oid: number
+  type_kind: 'base' | 'array' | 'enum' | 'range' | 'multirange' | 'domain' | 'composite' | 'pseudo'
 }
 
 export interface PgQueryResult {
+  query_id: string
   columns: PgColumnMeta[]
   rows: (string | number | boolean | null | object)[][]
   total_rows: number | null
+  estimated_rows: number | null
+  has_more: boolean
   affected_rows: number | null
   duration_ms: number
   query_type: string
 }
 
+// A bind parameter ($1 or :name) with the type the server inferred
+export interface PgParamMeta {
+  name: string
+  data_type: string
+}
+
+export interface PgQueryDescription {
+  params: PgParamMeta[]
+  columns: PgColumnMeta[]
+}
+
 export interface PgTableInfo {
   schema_name: string
   table_name: string
@@ -68,6 +99,59 @@ export interface PgSavedQuery {
   label: string
   sql_text: string
   created_at: string
+  params: PgSavedQueryParam[]
+}
+
+export interface PgSavedQueryParam {
+  name: string
+  data_type: string | null
+  default_value: string | number | boolean | null
+}
+
+export interface SqlStatement {
+  text: string
+  line: number
+}
+
+export interface PgStatementResult {
+  index: number
+  sql: string
+  line: number
+  query_type: string
+  status: 'ok' | 'error' | 'skipped'
+  result: PgQueryResult | null
+  error: string | null
+  duration_ms: number
+}
+
+export interface PgTransactionStatus {
+  started_at: string
+  // ms since the last statement finished; 0 while one is running
+  idle_ms: number
+  // a statement failed; only ROLLBACK (or ROLLBACK TO a savepoint) works now
+  failed: boolean
+}
+
+// Values by column name, as JSON
+export type PgRowValues = Record<string, unknown>
+
+// A grid edit; `original` is the row as read, used to detect conflicts
+export type PgRowChange =
+  | { kind: 'update'; key: PgRowValues; original?: PgRowValues; values: PgRowValues }
+  | { kind: 'insert'; values: PgRowValues }
+  | { kind: 'delete'; key: PgRowValues; original?: PgRowValues }
+
+export interface PgRowConflict {
+  index: number
+  reason: 'modified' | 'deleted'
+  current: PgRowValues | null
+}
+
+export interface PgRowChangeResult {
+  // false when a conflict stopped the batch; nothing was changed then
+  applied: boolean
+  affected_rows: number
+  conflicts: PgRowConflict[] // at most one: the first conflict found
 }
 
 export interface QueryTab {
@@ -76,6 +160,12 @@ export interface QueryTab {
   sql: string
   cursorPos: number
   result: PgQueryResult | null
+  // per-statement results when the tab ran a multi-statement script
+  scriptResults: PgStatementResult[] | null
+  // parameters of the last described query and the values entered for them;
+  // an empty value binds NULL
+  params: PgParamMeta[]
+  paramValues: Record<string, string>
   error: string | null
   loading: boolean
 }